@@ -0,0 +1,76 @@
+//! The advisories document a constellation can publish next to its metadata, linked from
+//! `advisories_url`.
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::error::CoreError;
+use crate::version::VersionReq;
+
+/// The newest advisories schema this build understands. Documents without a `schema_version`
+/// are treated as version 1.
+pub const ADVISORY_SCHEMA_VERSION: u32 = 1;
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Advisory {
+    pub id: String,
+    pub package: String,
+    /// Version ranges the advisory applies to; a version is affected if any of them matches.
+    pub affected: Vec<VersionReq>,
+    pub severity: Severity,
+    pub description: String,
+}
+
+impl Advisory {
+    pub fn affects(&self, name: &str, version: &str) -> bool {
+        self.package == name && self.affected.iter().any(|req| req.matches(version))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdvisoryList {
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+    pub advisories: Vec<Advisory>,
+}
+
+impl AdvisoryList {
+    /// Parses an advisories document, rejecting schema versions newer than `ADVISORY_SCHEMA_VERSION`.
+    pub fn from_json(content: &str) -> Result<Self, CoreError> {
+        let list: Self = serde_json::from_str(content).map_err(|source| CoreError::Parse {
+            context: "advisories".to_string(),
+            source,
+        })?;
+        if list.schema_version > ADVISORY_SCHEMA_VERSION {
+            return Err(CoreError::UnsupportedSchema {
+                found: list.schema_version,
+                supported: ADVISORY_SCHEMA_VERSION,
+            });
+        }
+        Ok(list)
+    }
+}
@@ -0,0 +1,83 @@
+//! The user's constellation config.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::error::CoreError;
+use crate::fetch;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Constellation {
+    pub name: String,
+    /// An `http(s)://` URL, a `file://` URL or a plain filesystem path. A relative path is relative
+    /// to the config file it was loaded from.
+    pub metadata_url: String,
+    /// Fallback metadata locations, tried in order after `metadata_url`.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    /// Try mirrors fastest-first instead of in the configured order.
+    #[serde(default)]
+    pub rank_mirrors: bool,
+    /// When several constellations provide a package, the highest priority wins regardless of version.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl Constellation {
+    pub fn default_constellations() -> Vec<Self> {
+        vec![
+            Constellation {
+                name: "Hoshi Core".to_string(),
+                metadata_url: "http://localhost:8000/hoshi-core-constellation.json".to_string(),
+                mirrors: Vec::new(),
+                rank_mirrors: false,
+                priority: 0,
+            },
+        ]
+    }
+
+    pub fn get_config_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("hoshi")
+            .join("constellations.json")
+    }
+
+    /// Loads the configured constellations, falling back to the defaults when no config file exists.
+    pub async fn load_all() -> Result<Vec<Self>, CoreError> {
+        Self::load_from(&Self::get_config_path()).await
+    }
+
+    /// Like `load_all`, reading the config from `path`. Relative locations are resolved against
+    /// the directory of the file `path` links to, so a config symlinked from an offline bundle
+    /// finds the bundle wherever it is mounted.
+    pub async fn load_from(path: &Path) -> Result<Vec<Self>, CoreError> {
+        if !path.exists() {
+            return Ok(Self::default_constellations());
+        }
+
+        let content = tokio::fs::read_to_string(path).await
+            .map_err(|source| CoreError::Io { path: path.to_path_buf(), source })?;
+        let mut constellations: Vec<Self> = serde_json::from_str(&content).map_err(|source| CoreError::Parse {
+            context: format!("constellation config {}", path.display()),
+            source,
+        })?;
+
+        let base = tokio::fs::canonicalize(path).await
+            .map_err(|source| CoreError::Io { path: path.to_path_buf(), source })?;
+        let base = base.display().to_string();
+        for constellation in &mut constellations {
+            constellation.metadata_url = fetch::resolve_location(&base, &constellation.metadata_url);
+            for mirror in &mut constellation.mirrors {
+                *mirror = fetch::resolve_location(&base, mirror);
+            }
+        }
+        Ok(constellations)
+    }
+
+    pub fn metadata_locations(&self) -> Vec<String> {
+        std::iter::once(self.metadata_url.clone())
+            .chain(self.mirrors.iter().cloned())
+            .collect()
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum CoreError {
+    /// A config file exists but could not be read.
+    Io { path: PathBuf, source: io::Error },
+    /// A config file or metadata document is not valid JSON for its schema.
+    Parse { context: String, source: serde_json::Error },
+    /// The metadata was written for a newer schema than this build understands.
+    UnsupportedSchema { found: u32, supported: u32 },
+    /// A version constraint such as `>=1.2, <2` could not be parsed.
+    InvalidVersionReq { input: String, reason: String },
+    /// A license expression such as `MIT OR Apache-2.0` could not be parsed.
+    InvalidLicense { input: String, reason: String },
+    /// A remote location could not be reached or answered with an HTTP error.
+    Network { location: String, reason: String },
+    /// A constellation has no metadata locations to fetch from.
+    NoMetadataLocations { constellation: String },
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoreError::Io { path, source } => write!(f, "Failed to read {}: {}", path.display(), source),
+            CoreError::Parse { context, source } => write!(f, "Invalid {}: {}", context, source),
+            CoreError::UnsupportedSchema { found, supported } => write!(
+                f,
+                "metadata schema version {} is newer than the supported version {}",
+                found, supported
+            ),
+            CoreError::InvalidVersionReq { input, reason } => write!(f, "Invalid version constraint '{}': {}", input, reason),
+            CoreError::InvalidLicense { input, reason } => write!(f, "Invalid license expression '{}': {}", input, reason),
+            CoreError::Network { location, reason } => write!(f, "{}: {}", location, reason),
+            CoreError::NoMetadataLocations { constellation } => write!(f, "No metadata locations configured for {}.", constellation),
+        }
+    }
+}
+
+impl std::error::Error for CoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CoreError::Io { source, .. } => Some(source),
+            CoreError::Parse { source, .. } => Some(source),
+            CoreError::UnsupportedSchema { .. }
+            | CoreError::InvalidVersionReq { .. }
+            | CoreError::InvalidLicense { .. }
+            | CoreError::Network { .. }
+            | CoreError::NoMetadataLocations { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Fetching constellation metadata from its configured locations, each an `http(s)://` URL, a
+//! `file://` URL or a plain path.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::constellation::Constellation;
+use crate::error::CoreError;
+use crate::metadata::ConstellationMetadata;
+
+pub fn is_remote(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Returns the filesystem path for a `file://` URL or a plain path, or `None` for remote URLs.
+pub fn local_path(location: &str) -> Option<PathBuf> {
+    if is_remote(location) {
+        return None;
+    }
+    match location.strip_prefix("file://") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => Some(PathBuf::from(location)),
+    }
+}
+
+pub async fn fetch_to_string(location: &str) -> Result<String, CoreError> {
+    match local_path(location) {
+        Some(path) => tokio::fs::read_to_string(&path).await
+            .map_err(|source| CoreError::Io { path, source }),
+        None => webfetch::fetch_url_to_string(location).await
+            .map_err(|e| CoreError::Network { location: location.to_string(), reason: e.to_string() }),
+    }
+}
+
+pub async fn measure_latency(location: &str) -> Option<Duration> {
+    match local_path(location) {
+        Some(path) => path.exists().then_some(Duration::ZERO),
+        None => webfetch::measure_latency(location).await.ok(),
+    }
+}
+
+/// Orders locations from fastest to slowest; unreachable ones keep their order at the end.
+pub async fn rank_by_latency(locations: Vec<String>) -> Vec<String> {
+    let mut measured = Vec::with_capacity(locations.len());
+    for location in locations {
+        let latency = measure_latency(&location).await;
+        measured.push((latency, location));
+    }
+    measured.sort_by_key(|(latency, _)| latency.unwrap_or(Duration::MAX));
+    measured.into_iter().map(|(_, location)| location).collect()
+}
+
+/// Resolves a location named in constellation metadata, such as a package download URL, against
+/// the location of the metadata.
+/// Absolute URLs are returned unchanged. An absolute path is relative to the server root when the
+/// metadata is remote, and a local path otherwise.
+pub fn resolve_location(base: &str, location: &str) -> String {
+    if location.contains("://") {
+        return location.to_string();
+    }
+    if is_remote(base) && location.starts_with('/') {
+        return format!("{}{}", origin(base), location);
+    }
+    if Path::new(location).is_absolute() {
+        return location.to_string();
+    }
+    match base.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/{}", dir, location.trim_start_matches("./")),
+        None => location.to_string(),
+    }
+}
+
+/// `scheme://host[:port]` of a remote URL.
+fn origin(url: &str) -> &str {
+    let authority_start = url.find("://").map_or(0, |i| i + 3);
+    match url[authority_start..].find('/') {
+        Some(i) => &url[..authority_start + i],
+        None => url,
+    }
+}
+
+/// Fetches the metadata from the first mirror that responds with valid JSON.
+/// Returns the metadata and the location that served it. Download and advisory locations are
+/// resolved against the mirrors, serving mirror first, and every package is tagged with the
+/// constellation's configured name.
+pub async fn fetch_metadata(constellation: &Constellation) -> Result<(ConstellationMetadata, String), CoreError> {
+    let mut locations = constellation.metadata_locations();
+    if constellation.rank_mirrors {
+        locations = rank_by_latency(locations).await;
+    }
+
+    let mut last_error: Option<CoreError> = None;
+    for (i, location) in locations.iter().enumerate() {
+        let content = match fetch_to_string(location).await {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Mirror {} failed for {}: {}", location, constellation.name, e);
+                last_error = Some(e);
+                continue;
+            }
+        };
+        let mut meta = match ConstellationMetadata::from_json(&content) {
+            Ok(meta) => meta,
+            Err(e) => {
+                eprintln!("Mirror {} served invalid metadata for {}: {}", location, constellation.name, e);
+                last_error = Some(e);
+                continue;
+            }
+        };
+
+        let served_by = location.clone();
+        meta.advisories_url = meta.advisories_url.map(|url| resolve_location(&served_by, &url));
+        locations.rotate_left(i);
+        for pkg in &mut meta.packages {
+            let mut urls: Vec<String> = Vec::new();
+            for base in &locations {
+                let url = resolve_location(base, &pkg.download_url);
+                if !urls.contains(&url) {
+                    urls.push(url);
+                }
+            }
+            pkg.download_url = urls[0].clone();
+            pkg.mirror_urls = urls;
+            pkg.constellation = constellation.name.clone();
+        }
+        return Ok((meta, served_by));
+    }
+
+    Err(last_error.unwrap_or_else(|| CoreError::NoMetadataLocations { constellation: constellation.name.clone() }))
+}
@@ -0,0 +1,18 @@
+//! Types shared by every Hoshi tool: the constellation metadata and advisory schemas, the
+//! constellation config, version ordering and license expressions, plus fetching constellation
+//! metadata. Downloading and installing packages live in `hoshipkg`.
+
+pub mod advisory;
+pub mod constellation;
+pub mod error;
+pub mod fetch;
+pub mod license;
+pub mod metadata;
+pub mod version;
+
+pub use advisory::{Advisory, AdvisoryList, Severity, ADVISORY_SCHEMA_VERSION};
+pub use constellation::Constellation;
+pub use error::CoreError;
+pub use license::LicenseExpr;
+pub use metadata::{ConstellationMetadata, PackageMetadata, SCHEMA_VERSION};
+pub use version::{Version, VersionReq};
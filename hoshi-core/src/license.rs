@@ -0,0 +1,167 @@
+//! SPDX license expressions such as `MIT OR Apache-2.0` or `GPL-2.0-only WITH Classpath-exception-2.0`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::CoreError;
+
+/// A parsed license expression. `WITH` binds tighter than `AND`, which binds tighter than `OR`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseExpr {
+    License { id: String, exception: Option<String> },
+    And(Vec<LicenseExpr>),
+    Or(Vec<LicenseExpr>),
+}
+
+impl LicenseExpr {
+    /// Whether some choice of licenses the expression offers is acceptable: every operand of an
+    /// `AND`, and at least one of an `OR`. Exceptions don't affect the outcome.
+    pub fn is_satisfied_by(&self, accept: &impl Fn(&str) -> bool) -> bool {
+        match self {
+            LicenseExpr::License { id, .. } => accept(id),
+            LicenseExpr::And(operands) => operands.iter().all(|e| e.is_satisfied_by(accept)),
+            LicenseExpr::Or(operands) => operands.iter().any(|e| e.is_satisfied_by(accept)),
+        }
+    }
+
+    /// Every license identifier in the expression, in order of appearance.
+    pub fn licenses(&self) -> Vec<&str> {
+        match self {
+            LicenseExpr::License { id, .. } => vec![id.as_str()],
+            LicenseExpr::And(operands) | LicenseExpr::Or(operands) => operands.iter().flat_map(|e| e.licenses()).collect(),
+        }
+    }
+}
+
+impl FromStr for LicenseExpr {
+    type Err = CoreError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { input, tokens: tokenize(input), pos: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(parser.invalid(&format!("unexpected '{}'", token))),
+        }
+    }
+}
+
+impl fmt::Display for LicenseExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, operands: &[LicenseExpr], op: &str| {
+            for (i, operand) in operands.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                // Only an OR inside an AND needs parentheses to keep its meaning.
+                if op == "AND" && matches!(operand, LicenseExpr::Or(_)) {
+                    write!(f, "({})", operand)?;
+                } else {
+                    write!(f, "{}", operand)?;
+                }
+            }
+            Ok(())
+        };
+        match self {
+            LicenseExpr::License { id, exception: None } => f.write_str(id),
+            LicenseExpr::License { id, exception: Some(exception) } => write!(f, "{} WITH {}", id, exception),
+            LicenseExpr::And(operands) => join(f, operands, "AND"),
+            LicenseExpr::Or(operands) => join(f, operands, "OR"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in input.char_indices() {
+        if c.is_whitespace() || c == '(' || c == ')' {
+            if let Some(s) = start.take() {
+                tokens.push(&input[s..i]);
+            }
+            if !c.is_whitespace() {
+                tokens.push(&input[i..i + 1]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(&input[s..]);
+    }
+    tokens
+}
+
+fn is_operator(token: &str, op: &str) -> bool {
+    token.eq_ignore_ascii_case(op)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn invalid(&self, reason: &str) -> CoreError {
+        CoreError::InvalidLicense { input: self.input.to_string(), reason: reason.to_string() }
+    }
+
+    fn peek_operator(&self, op: &str) -> bool {
+        self.tokens.get(self.pos).is_some_and(|t| is_operator(t, op))
+    }
+
+    fn or(&mut self) -> Result<LicenseExpr, CoreError> {
+        let mut operands = vec![self.and()?];
+        while self.peek_operator("OR") {
+            self.pos += 1;
+            operands.push(self.and()?);
+        }
+        Ok(if operands.len() == 1 { operands.remove(0) } else { LicenseExpr::Or(operands) })
+    }
+
+    fn and(&mut self) -> Result<LicenseExpr, CoreError> {
+        let mut operands = vec![self.primary()?];
+        while self.peek_operator("AND") {
+            self.pos += 1;
+            operands.push(self.primary()?);
+        }
+        Ok(if operands.len() == 1 { operands.remove(0) } else { LicenseExpr::And(operands) })
+    }
+
+    fn primary(&mut self) -> Result<LicenseExpr, CoreError> {
+        let Some(&token) = self.tokens.get(self.pos) else {
+            return Err(self.invalid("expected a license"));
+        };
+        self.pos += 1;
+
+        if token == "(" {
+            let expr = self.or()?;
+            if self.tokens.get(self.pos) != Some(&")") {
+                return Err(self.invalid("missing ')'"));
+            }
+            self.pos += 1;
+            return Ok(expr);
+        }
+        let id = self.identifier(token)?;
+
+        let mut exception = None;
+        if self.peek_operator("WITH") {
+            self.pos += 1;
+            let Some(&token) = self.tokens.get(self.pos) else {
+                return Err(self.invalid("expected an exception after WITH"));
+            };
+            self.pos += 1;
+            exception = Some(self.identifier(token)?);
+        }
+        Ok(LicenseExpr::License { id, exception })
+    }
+
+    fn identifier(&self, token: &str) -> Result<String, CoreError> {
+        let valid = token.chars().all(|c| c.is_ascii_alphanumeric() || "-.+:".contains(c));
+        if !valid || token == ")" || ["AND", "OR", "WITH"].iter().any(|op| is_operator(token, op)) {
+            return Err(self.invalid(&format!("'{}' is not a license identifier", token)));
+        }
+        Ok(token.to_string())
+    }
+}
@@ -0,0 +1,83 @@
+//! The metadata document a constellation publishes.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CoreError;
+
+/// The newest metadata schema this build understands. Documents without a
+/// `schema_version` are treated as version 1.
+pub const SCHEMA_VERSION: u32 = 1;
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PackageMetadata {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub download_url: String,
+    pub size_mb: u32,
+    /// Size once extracted. Falls back to `size_mb` when a constellation doesn't publish it.
+    #[serde(default)]
+    pub installed_size_mb: Option<u32>,
+    pub archive_type: String,
+    pub dependencies: Option<Vec<String>>,
+    /// Lowercase hex SHA-256 of the archive.
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// An SPDX license expression such as `MIT OR Apache-2.0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Programs to put on the PATH, relative to the package root. When absent, hoshipkg uses the
+    /// files under `bin/` and executable files at the top level of the package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executables: Option<Vec<String>>,
+    /// The download location on every mirror, serving mirror first. Filled in at sync time.
+    #[serde(skip)]
+    pub mirror_urls: Vec<String>,
+    /// The configured name of the constellation that provides this package. Filled in at sync time.
+    #[serde(skip)]
+    pub constellation: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ConstellationMetadata {
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+    pub name: String,
+    pub description: String,
+    pub packages: Vec<PackageMetadata>,
+    /// Where the constellation publishes security advisories, relative to the metadata location
+    /// or absolute. Resolved to a full location at sync time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advisories_url: Option<String>,
+}
+
+impl ConstellationMetadata {
+    pub fn new(name: String, description: String, packages: Vec<PackageMetadata>) -> Self {
+        ConstellationMetadata {
+            schema_version: SCHEMA_VERSION,
+            name,
+            description,
+            packages,
+            advisories_url: None,
+        }
+    }
+
+    /// Parses a metadata document, rejecting schema versions newer than `SCHEMA_VERSION`.
+    pub fn from_json(content: &str) -> Result<Self, CoreError> {
+        let meta: Self = serde_json::from_str(content).map_err(|source| CoreError::Parse {
+            context: "constellation metadata".to_string(),
+            source,
+        })?;
+        if meta.schema_version > SCHEMA_VERSION {
+            return Err(CoreError::UnsupportedSchema {
+                found: meta.schema_version,
+                supported: SCHEMA_VERSION,
+            });
+        }
+        Ok(meta)
+    }
+}
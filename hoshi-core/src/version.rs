@@ -0,0 +1,238 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+use crate::error::CoreError;
+
+/// Compares dotted version strings component by component, numerically where both sides are numbers.
+/// `1.10` sorts after `1.9`, and `1.0` equals `1.0.0`. A pre-release such as `1.0-rc1` sorts below
+/// `1.0` itself, and build metadata such as `+build5` is ignored.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a_release, a_pre) = split_version(a);
+    let (b_release, b_pre) = split_version(b);
+
+    compare_release(a_release, b_release).then_with(|| match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a_pre), Some(b_pre)) => compare_pre_release(a_pre, b_pre),
+    })
+}
+
+/// Drops the build metadata and splits off the pre-release.
+fn split_version(version: &str) -> (&str, Option<&str>) {
+    let version = version.split_once('+').map_or(version, |(version, _)| version);
+    match version.split_once('-') {
+        Some((release, pre)) => (release, Some(pre)),
+        None => (version, None),
+    }
+}
+
+fn compare_component(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num),
+        _ => a.cmp(b),
+    }
+}
+
+/// Missing trailing components count as zero.
+fn compare_release(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split('.');
+    let mut b_parts = b.split('.');
+
+    loop {
+        match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(a_part), None) => {
+                if a_part.trim_start_matches('0').is_empty() {
+                    continue;
+                }
+                return Ordering::Greater;
+            },
+            (None, Some(b_part)) => {
+                if b_part.trim_start_matches('0').is_empty() {
+                    continue;
+                }
+                return Ordering::Less;
+            },
+            (Some(a_part), Some(b_part)) => {
+                let ordering = compare_component(a_part, b_part);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            },
+        }
+    }
+}
+
+/// A pre-release with more components sorts above one it extends, so `rc.1` is above `rc`.
+fn compare_pre_release(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split(['.', '-']);
+    let mut b_parts = b.split(['.', '-']);
+
+    loop {
+        match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(a_part), Some(b_part)) => {
+                let ordering = compare_component(a_part, b_part);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            },
+        }
+    }
+}
+
+/// A package version ordered by `compare_versions`, so `1.0` and `1.0.0` are equal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Version(String);
+
+impl Version {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Version {
+    fn from(version: &str) -> Self {
+        Version(version.to_string())
+    }
+}
+
+impl From<String> for Version {
+    fn from(version: String) -> Self {
+        Version(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_versions(&self.0, &other.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+        }
+    }
+}
+
+/// A version constraint: comma-separated comparisons that must all hold, e.g. `>=1.2, <2`.
+/// A bare version means `=`, and `*` matches every version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct VersionReq {
+    comparators: Vec<(Op, Version)>,
+}
+
+impl VersionReq {
+    /// Matches every version.
+    pub fn any() -> Self {
+        VersionReq { comparators: Vec::new() }
+    }
+
+    pub fn matches(&self, version: &str) -> bool {
+        self.comparators.iter().all(|(op, wanted)| {
+            let ordering = compare_versions(version, wanted.as_str());
+            match op {
+                Op::Eq => ordering == Ordering::Equal,
+                Op::Gt => ordering == Ordering::Greater,
+                Op::Ge => ordering != Ordering::Less,
+                Op::Lt => ordering == Ordering::Less,
+                Op::Le => ordering != Ordering::Greater,
+            }
+        })
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = CoreError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| CoreError::InvalidVersionReq { input: input.to_string(), reason: reason.to_string() };
+        if input.trim() == "*" {
+            return Ok(VersionReq::any());
+        }
+
+        let mut comparators = Vec::new();
+        for part in input.split(',').map(str::trim) {
+            // Two-character operators first, so `>=` isn't read as `>` followed by `=1.0`.
+            let (op, version) = [Op::Ge, Op::Le, Op::Gt, Op::Lt, Op::Eq].into_iter()
+                .find_map(|op| part.strip_prefix(op.symbol()).map(|rest| (op, rest.trim())))
+                .unwrap_or((Op::Eq, part));
+            if version.is_empty() {
+                return Err(invalid("expected a version after each operator"));
+            }
+            if version.contains(|c: char| c.is_whitespace() || "<>=*".contains(c)) {
+                return Err(invalid(&format!("'{}' is not a version", version)));
+            }
+            comparators.push((op, Version::from(version)));
+        }
+        Ok(VersionReq { comparators })
+    }
+}
+
+impl TryFrom<String> for VersionReq {
+    type Error = CoreError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+impl From<VersionReq> for String {
+    fn from(req: VersionReq) -> Self {
+        req.to_string()
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.comparators.is_empty() {
+            return f.write_str("*");
+        }
+        let parts: Vec<String> = self.comparators.iter()
+            .map(|(op, version)| format!("{}{}", op.symbol(), version))
+            .collect();
+        f.write_str(&parts.join(", "))
+    }
+}
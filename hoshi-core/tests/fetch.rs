@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+
+use hoshi_core::fetch::{fetch_metadata, resolve_location};
+use hoshi_core::{Constellation, CoreError};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+}
+
+fn constellation(metadata_url: &str, mirrors: &[&str]) -> Constellation {
+    Constellation {
+        name: "Local Core".to_string(),
+        metadata_url: metadata_url.to_string(),
+        mirrors: mirrors.iter().map(|m| m.to_string()).collect(),
+        rank_mirrors: false,
+        priority: 0,
+    }
+}
+
+#[test]
+fn resolves_relative_locations_against_the_metadata_directory() {
+    let base = "https://hoshi.example.org/core/constellation.json";
+    assert_eq!(resolve_location(base, "packages/hello-1.0.tar.gz"), "https://hoshi.example.org/core/packages/hello-1.0.tar.gz");
+    assert_eq!(resolve_location(base, "./hello-1.0.tar.gz"), "https://hoshi.example.org/core/hello-1.0.tar.gz");
+    assert_eq!(resolve_location("/srv/core/constellation.json", "hello-1.0.tar.gz"), "/srv/core/hello-1.0.tar.gz");
+    assert_eq!(resolve_location("file:///srv/core/constellation.json", "hello-1.0.tar.gz"), "file:///srv/core/hello-1.0.tar.gz");
+}
+
+#[test]
+fn resolves_absolute_locations() {
+    let base = "https://hoshi.example.org:8443/core/constellation.json";
+    assert_eq!(resolve_location(base, "https://cdn.example.org/hello.tar.gz"), "https://cdn.example.org/hello.tar.gz");
+    assert_eq!(resolve_location(base, "/pool/hello.tar.gz"), "https://hoshi.example.org:8443/pool/hello.tar.gz");
+    assert_eq!(resolve_location("https://hoshi.example.org", "/pool/hello.tar.gz"), "https://hoshi.example.org/pool/hello.tar.gz");
+    assert_eq!(resolve_location("file:///srv/core/constellation.json", "/pool/hello.tar.gz"), "/pool/hello.tar.gz");
+}
+
+#[tokio::test]
+async fn fetches_from_the_first_working_mirror() {
+    let missing = fixture("missing-constellation.json").display().to_string();
+    let core = format!("file://{}", fixture("core-constellation.json").display());
+    let (meta, served_by) = fetch_metadata(&constellation(&missing, &[&core])).await.unwrap();
+
+    assert_eq!(served_by, core);
+    let archive = fixture("packages").join("hello-1.0.tar.gz").display().to_string();
+    let hello = &meta.packages[0];
+    assert_eq!(hello.constellation, "Local Core");
+    assert_eq!(hello.download_url, format!("file://{}", archive));
+    assert_eq!(hello.mirror_urls, [format!("file://{}", archive), archive]);
+    assert_eq!(meta.packages[1].download_url, "https://mirror.example.org/core/world-0.3.zip");
+}
+
+#[tokio::test]
+async fn reports_the_last_mirror_error() {
+    let future = fixture("future-constellation.json").display().to_string();
+    let missing = fixture("missing-constellation.json").display().to_string();
+
+    let err = fetch_metadata(&constellation(&missing, &[&future])).await.unwrap_err();
+    assert!(matches!(err, CoreError::UnsupportedSchema { .. }));
+    let err = fetch_metadata(&constellation(&future, &[&missing])).await.unwrap_err();
+    assert!(matches!(err, CoreError::Io { .. }));
+}
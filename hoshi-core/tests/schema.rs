@@ -0,0 +1,223 @@
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+use hoshi_core::version::compare_versions;
+use hoshi_core::{
+    AdvisoryList, Constellation, ConstellationMetadata, CoreError, LicenseExpr, Severity, Version, VersionReq,
+    ADVISORY_SCHEMA_VERSION, SCHEMA_VERSION,
+};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+}
+
+fn read_fixture(name: &str) -> String {
+    std::fs::read_to_string(fixture(name)).unwrap()
+}
+
+#[test]
+fn parses_current_schema() {
+    let meta = ConstellationMetadata::from_json(&read_fixture("core-constellation.json")).unwrap();
+
+    assert_eq!(meta.schema_version, SCHEMA_VERSION);
+    assert_eq!(meta.name, "Hoshi Core");
+    assert_eq!(meta.packages.len(), 2);
+
+    let hello = &meta.packages[0];
+    assert_eq!(hello.size_mb, 1);
+    assert_eq!(hello.installed_size_mb, Some(3));
+    assert_eq!(hello.dependencies, None);
+    assert!(hello.checksum.is_some());
+
+    let world = &meta.packages[1];
+    assert_eq!(world.installed_size_mb, None);
+    assert_eq!(world.checksum, None);
+    assert_eq!(world.dependencies.as_deref(), Some(&["hello".to_string(), "libstar@Hoshi Extra".to_string()][..]));
+}
+
+#[test]
+fn runtime_fields_are_not_part_of_the_schema() {
+    let mut meta = ConstellationMetadata::from_json(&read_fixture("core-constellation.json")).unwrap();
+    assert!(meta.packages.iter().all(|p| p.mirror_urls.is_empty() && p.constellation.is_empty()));
+
+    meta.packages[0].mirror_urls.push("https://mirror.example.org/core/hello-1.0.tar.gz".to_string());
+    meta.packages[0].constellation = "Hoshi Core".to_string();
+    let json = serde_json::to_value(&meta).unwrap();
+    let hello = &json["packages"][0];
+    assert!(hello.get("mirror_urls").is_none());
+    assert!(hello.get("constellation").is_none());
+}
+
+#[test]
+fn missing_schema_version_is_version_one() {
+    let meta = ConstellationMetadata::from_json(&read_fixture("legacy-constellation.json")).unwrap();
+    assert_eq!(meta.schema_version, 1);
+    assert_eq!(meta.packages[0].name, "libstar");
+}
+
+#[test]
+fn rejects_newer_schema() {
+    match ConstellationMetadata::from_json(&read_fixture("future-constellation.json")) {
+        Err(CoreError::UnsupportedSchema { found, supported }) => {
+            assert_eq!(found, 99);
+            assert_eq!(supported, SCHEMA_VERSION);
+        },
+        other => panic!("expected UnsupportedSchema, got {:?}", other),
+    }
+}
+
+#[test]
+fn rejects_metadata_missing_required_fields() {
+    let result = ConstellationMetadata::from_json(r#"{"name": "Broken", "packages": []}"#);
+    assert!(matches!(result, Err(CoreError::Parse { .. })));
+}
+
+#[test]
+fn round_trips_through_serde() {
+    let meta = ConstellationMetadata::from_json(&read_fixture("legacy-constellation.json")).unwrap();
+    let json = serde_json::to_string(&meta).unwrap();
+    let reparsed = ConstellationMetadata::from_json(&json).unwrap();
+
+    assert_eq!(reparsed.schema_version, SCHEMA_VERSION);
+    assert_eq!(reparsed.packages[0].version, meta.packages[0].version);
+    assert_eq!(reparsed.packages[0].download_url, meta.packages[0].download_url);
+}
+
+#[tokio::test]
+async fn loads_constellation_config_with_defaults() {
+    let constellations = Constellation::load_from(&fixture("constellations.json")).await.unwrap();
+    assert_eq!(constellations.len(), 2);
+
+    let core = &constellations[0];
+    assert!(core.rank_mirrors);
+    assert_eq!(core.priority, 10);
+    assert_eq!(core.metadata_locations(), vec![
+        "https://hoshi.example.org/core/constellation.json".to_string(),
+        "https://mirror.example.org/core/constellation.json".to_string(),
+    ]);
+
+    let local = &constellations[1];
+    assert!(local.mirrors.is_empty());
+    assert!(!local.rank_mirrors);
+    assert_eq!(local.priority, 0);
+}
+
+#[tokio::test]
+async fn resolves_relative_locations_against_the_config_file() {
+    let constellations = Constellation::load_from(&fixture("bundle-constellations.json")).await.unwrap();
+    let dir = fixture("").canonicalize().unwrap();
+
+    assert_eq!(constellations[0].metadata_locations(), vec![
+        dir.join("local-core").join("constellation.json").display().to_string(),
+        dir.join("mirror").join("constellation.json").display().to_string(),
+        "https://hoshi.example.org/core/constellation.json".to_string(),
+    ]);
+}
+
+#[tokio::test]
+async fn missing_constellation_config_falls_back_to_defaults() {
+    let constellations = Constellation::load_from(&fixture("does-not-exist.json")).await.unwrap();
+    assert_eq!(constellations.len(), Constellation::default_constellations().len());
+}
+
+#[test]
+fn orders_versions_numerically() {
+    assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+    assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Equal);
+    assert_eq!(compare_versions("2.0-rc1", "2.0-rc2"), Ordering::Less);
+    assert_eq!(compare_versions("2.0-rc.2", "2.0-rc.10"), Ordering::Less);
+    assert_eq!(compare_versions("2.0-rc", "2.0-rc.1"), Ordering::Less);
+
+    assert_eq!(Version::from("1.0"), Version::from("1.0.0"));
+    assert!(Version::from("0.10") > Version::from("0.9.9"));
+}
+
+#[test]
+fn orders_pre_releases_below_the_release() {
+    assert_eq!(compare_versions("1.0-rc1", "1.0"), Ordering::Less);
+    assert_eq!(compare_versions("1.0.0-alpha", "1.0"), Ordering::Less);
+    assert_eq!(compare_versions("1.0-rc1", "0.9"), Ordering::Greater);
+    assert_eq!(compare_versions("1.1-beta", "1.0"), Ordering::Greater);
+
+    let below_two: VersionReq = "<2".parse().unwrap();
+    assert!(below_two.matches("2.0-rc1"));
+    assert!(!below_two.matches("2.0"));
+}
+
+#[test]
+fn ignores_build_metadata() {
+    assert_eq!(compare_versions("1.0+build5", "1.0"), Ordering::Equal);
+    assert_eq!(compare_versions("1.0+build5", "1.0+build6"), Ordering::Equal);
+    assert_eq!(compare_versions("1.0-rc1+build5", "1.0-rc1"), Ordering::Equal);
+    assert_eq!(compare_versions("1.0+build-7", "1.0"), Ordering::Equal);
+    assert_eq!(Version::from("1.0.0+linux"), Version::from("1.0"));
+}
+
+#[test]
+fn matches_version_constraints() {
+    let req: VersionReq = ">=1.2, <2".parse().unwrap();
+    assert!(req.matches("1.2.0"));
+    assert!(req.matches("1.10"));
+    assert!(!req.matches("1.1"));
+    assert!(!req.matches("2.0"));
+    assert_eq!(req.to_string(), ">=1.2, <2");
+
+    let exact: VersionReq = "1.0".parse().unwrap();
+    assert!(exact.matches("1.0.0"));
+    assert!(!exact.matches("1.0.1"));
+    assert!("*".parse::<VersionReq>().unwrap().matches("0.1"));
+}
+
+#[test]
+fn rejects_malformed_version_constraints() {
+    for input in [">=", "1.0,", ">> 1.0", ">=1.0 <2"] {
+        assert!(
+            matches!(input.parse::<VersionReq>(), Err(CoreError::InvalidVersionReq { .. })),
+            "{} should be rejected",
+            input
+        );
+    }
+}
+
+#[test]
+fn evaluates_license_expressions() {
+    let expr: LicenseExpr = "MIT OR (Apache-2.0 AND GPL-2.0-only WITH Classpath-exception-2.0)".parse().unwrap();
+    assert_eq!(expr.licenses(), ["MIT", "Apache-2.0", "GPL-2.0-only"]);
+    assert_eq!(expr.to_string(), "MIT OR Apache-2.0 AND GPL-2.0-only WITH Classpath-exception-2.0");
+
+    assert!(expr.is_satisfied_by(&|id| id == "MIT"));
+    assert!(expr.is_satisfied_by(&|id| id != "MIT"));
+    assert!(!expr.is_satisfied_by(&|id| id == "Apache-2.0"));
+
+    let nested: LicenseExpr = "(MIT or ISC) and Zlib".parse().unwrap();
+    assert_eq!(nested.to_string(), "(MIT OR ISC) AND Zlib");
+}
+
+#[test]
+fn rejects_malformed_license_expressions() {
+    for input in ["", "MIT OR", "(MIT", "MIT Apache-2.0", "AND MIT", "MIT WITH", "MIT/X11"] {
+        assert!(
+            matches!(input.parse::<LicenseExpr>(), Err(CoreError::InvalidLicense { .. })),
+            "{:?} should be rejected",
+            input
+        );
+    }
+}
+
+#[test]
+fn parses_advisories() {
+    let list = AdvisoryList::from_json(r#"{"advisories": [
+        {"id": "HOSHI-1", "package": "hello", "affected": ["<1.1", ">=2.0, <2.0.3"], "severity": "high", "description": "d"}
+    ]}"#).unwrap();
+    assert_eq!(list.schema_version, 1);
+
+    let advisory = &list.advisories[0];
+    assert!(advisory.affects("hello", "1.0"));
+    assert!(advisory.affects("hello", "2.0.1"));
+    assert!(!advisory.affects("hello", "1.1"));
+    assert!(!advisory.affects("world", "1.0"));
+    assert!(Severity::Critical > Severity::High && Severity::Medium > Severity::Low);
+
+    let future = format!(r#"{{"schema_version": {}, "advisories": []}}"#, ADVISORY_SCHEMA_VERSION + 1);
+    assert!(matches!(AdvisoryList::from_json(&future), Err(CoreError::UnsupportedSchema { .. })));
+}
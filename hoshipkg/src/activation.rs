@@ -0,0 +1,328 @@
+//! Selects which of the side-by-side installed versions of a package is in use.
+//!
+//! `<root>/<name>/active` links to the active version's directory. The shared `<root>/bin` holds a
+//! link to each of the active version's executables and `<root>/lib` mirrors its `lib/`, all going
+//! through `active`, e.g. `<root>/bin/hello -> ../hello/active/bin/hello`. Putting `<root>/bin` on
+//! the PATH (see `hpkg env`) makes every active package's programs available.
+
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use serde::Serialize;
+
+use crate::error::{HpkgError, Result};
+use crate::manifest::{self, InstalledFile};
+use crate::registry::{InstalledPackage, PackageRegistry};
+use crate::version;
+
+/// The shared directories of the symlink farm, relative to the install root.
+pub const FARM_DIRS: [&str; 2] = ["bin", "lib"];
+
+/// The per-package link to the active version.
+pub const ACTIVE_LINK: &str = "active";
+
+/// What switching versions did to the symlink farm.
+#[derive(Debug, Default, Serialize)]
+pub struct LinkReport {
+    /// Farm entries that now point at the package.
+    pub linked: Vec<PathBuf>,
+    /// Farm entries of the previously active version that were removed.
+    pub unlinked: Vec<PathBuf>,
+    /// Farm entries left alone because another package's file is already there.
+    pub conflicts: Vec<PathBuf>,
+}
+
+/// The install root a package was installed under, given `<root>/<name>/<version>`.
+pub fn install_root_of(package: &InstalledPackage) -> Option<&Path> {
+    package.install_path.parent()?.parent()
+}
+
+/// Makes `name` v`version` the active version, replacing the previous version's farm entries with
+/// its own. The choice is recorded in `registry`; saving it is up to the caller.
+pub async fn activate(registry: &mut PackageRegistry, name: &str, version: &str) -> Result<LinkReport> {
+    let package = find_installed(registry, name, version)?.clone();
+    let root = install_root(&package)?.to_path_buf();
+    let active_version = package.version.clone();
+
+    let report = run_blocking(move || {
+        let unlinked = unlink_farm(&root, &package.name)?;
+        link_active(&package)?;
+        let mut report = link_farm(&root, &package)?;
+        report.unlinked = unlinked.into_iter().filter(|link| !report.linked.contains(link)).collect();
+        Ok(report)
+    }).await?;
+    registry.set_active(name, Some(&active_version));
+    Ok(report)
+}
+
+/// Removes `package`'s `active` link and farm entries if it is the active version.
+pub async fn deactivate(registry: &mut PackageRegistry, package: &InstalledPackage) -> Result<LinkReport> {
+    if !registry.is_active(package) {
+        return Ok(LinkReport::default());
+    }
+    let root = install_root(package)?.to_path_buf();
+    let name = package.name.clone();
+    let unlinked = run_blocking(move || {
+        let unlinked = unlink_farm(&root, &name)?;
+        remove_link(&root.join(&name).join(ACTIVE_LINK))?;
+        Ok(unlinked)
+    }).await?;
+    registry.set_active(&package.name, None);
+    Ok(LinkReport { unlinked, ..LinkReport::default() })
+}
+
+/// Call after `removed` left the registry. If it was the active version, its links are removed and
+/// the newest version still installed takes over; otherwise nothing changes.
+pub async fn on_removed(registry: &mut PackageRegistry, removed: &InstalledPackage) -> Result<LinkReport> {
+    if !registry.is_active(removed) {
+        return Ok(LinkReport::default());
+    }
+    let mut report = deactivate(registry, removed).await?;
+    let Some(newest) = registry.versions_of(&removed.name).last().map(|p| p.version.clone()) else {
+        return Ok(report);
+    };
+    let activated = activate(registry, &removed.name, &newest).await?;
+    report.unlinked.retain(|link| !activated.linked.contains(link));
+    report.linked = activated.linked;
+    report.conflicts = activated.conflicts;
+    Ok(report)
+}
+
+/// The executables a package's metadata declares, skipping any it did not install.
+pub fn declared_executables(name: &str, declared: &[String], files: &[InstalledFile]) -> Vec<PathBuf> {
+    declared.iter()
+        .map(PathBuf::from)
+        .filter(|path| {
+            let installed = files.iter().any(|f| f.path == *path);
+            if !installed {
+                eprintln!("Warning: {} declares executable {}, but did not install it.", name, path.display());
+            }
+            installed
+        })
+        .collect()
+}
+
+/// The files under `bin/` plus executable files at the top level of the package.
+pub fn discover_executables(files: &[InstalledFile]) -> Vec<PathBuf> {
+    files.iter()
+        .filter(|f| {
+            let top_level = f.path.components().count() == 1;
+            let executable = f.mode.is_some_and(|mode| mode & 0o111 != 0);
+            f.path.starts_with(FARM_DIRS[0]) || (top_level && executable)
+        })
+        .map(|f| f.path.clone())
+        .collect()
+}
+
+/// Recreates every `active` link and farm entry from the registry, e.g. after a snapshot put older
+/// install directories back, and forgets active versions that are no longer installed.
+pub async fn relink(registry: &mut PackageRegistry) -> Result<()> {
+    let active: Vec<(String, String)> = registry.active_names().into_iter()
+        .filter_map(|name| registry.active_version(name).map(|v| (name.to_string(), v.to_string())))
+        .collect();
+    for (name, version) in active {
+        let installed = registry.versions_of(&name).iter().any(|p| version::compare_versions(&p.version, &version).is_eq());
+        if installed {
+            activate(registry, &name, &version).await?;
+        } else {
+            registry.set_active(&name, None);
+        }
+    }
+    Ok(())
+}
+
+/// Removes farm entries and `active` links under `root` whose targets no longer exist.
+pub async fn prune_dangling(root: &Path) -> Result<()> {
+    let root = root.to_path_buf();
+    run_blocking(move || {
+        for dir in FARM_DIRS {
+            let farm_dir = root.join(dir);
+            let mut links = Vec::new();
+            walk_links(&farm_dir, &mut links)?;
+            for link in links {
+                if !link.exists() {
+                    remove_link(&link)?;
+                    remove_empty_parents(&link, &farm_dir);
+                }
+            }
+        }
+        if !root.is_dir() {
+            return Ok(());
+        }
+        for entry in std::fs::read_dir(&root)? {
+            let active = entry?.path().join(ACTIVE_LINK);
+            if active.is_symlink() && !active.exists() {
+                remove_link(&active)?;
+            }
+        }
+        Ok(())
+    }).await
+}
+
+/// The installed `name` v`version`, or a `NotFound` error listing the versions that are installed.
+pub fn find_installed<'a>(registry: &'a PackageRegistry, name: &str, version: &str) -> Result<&'a InstalledPackage> {
+    let installed = registry.versions_of(name);
+    if let Some(package) = installed.iter().find(|p| version::compare_versions(&p.version, version).is_eq()) {
+        return Ok(package);
+    }
+    if installed.is_empty() {
+        return Err(HpkgError::NotFound(format!("Package '{}' is not installed.", name)));
+    }
+    let versions: Vec<&str> = installed.iter().map(|p| p.version.as_str()).collect();
+    Err(HpkgError::NotFound(format!("{} v{} is not installed; installed versions: {}.", name, version, versions.join(", "))))
+}
+
+fn install_root(package: &InstalledPackage) -> Result<&Path> {
+    install_root_of(package).ok_or_else(|| HpkgError::Io(format!(
+        "Install path {} of {} is not inside an install root.",
+        package.install_path.display(), package.name
+    )))
+}
+
+async fn run_blocking<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await?
+}
+
+/// Points `<root>/<name>/active` at the package's version directory, replacing the old link in one step.
+fn link_active(package: &InstalledPackage) -> Result<()> {
+    let (Some(package_dir), Some(version_dir)) = (package.install_path.parent(), package.install_path.file_name()) else {
+        return Err(HpkgError::Io(format!("Invalid install path {}.", package.install_path.display())));
+    };
+    let link = package_dir.join(ACTIVE_LINK);
+    if link.exists() && !link.is_symlink() {
+        return Err(HpkgError::Conflict(format!("{} exists and is not a link; not replacing it.", link.display())));
+    }
+
+    let staging = package_dir.join(format!(".{}.new", ACTIVE_LINK));
+    remove_link(&staging)?;
+    symlink(Path::new(version_dir), &staging)
+        .and_then(|()| std::fs::rename(&staging, &link))
+        .map_err(|e| HpkgError::Io(format!("Failed to link {}: {}", link.display(), e)))
+}
+
+/// Links the package's executables into `<root>/bin` and every file under its `lib/` into `<root>/lib`.
+fn link_farm(root: &Path, package: &InstalledPackage) -> Result<LinkReport> {
+    let files = if package.files.is_empty() {
+        // Entries recorded before file lists were kept.
+        manifest::scan_blocking(&package.install_path)?
+    } else {
+        package.files.clone()
+    };
+    let executables = package.executables.clone().unwrap_or_else(|| discover_executables(&files));
+
+    // Pairs of the farm entry, relative to `root`, and the file it exposes, relative to the package.
+    let executable_entries = executables.into_iter()
+        .filter_map(|path| Some((Path::new(FARM_DIRS[0]).join(path.file_name()?), path)));
+    let lib_entries = files.into_iter()
+        .map(|f| f.path)
+        .filter(|path| path.starts_with(FARM_DIRS[1]))
+        .map(|path| (path.clone(), path));
+
+    let mut report = LinkReport::default();
+    for (entry, path) in executable_entries.chain(lib_entries) {
+        let link = root.join(&entry);
+        let target = farm_target(&package.name, &entry, &path);
+
+        if link.is_symlink() || link.exists() {
+            let ours = std::fs::read_link(&link).is_ok_and(|existing| existing == target);
+            if !ours {
+                report.conflicts.push(link);
+            } else if !report.linked.contains(&link) {
+                report.linked.push(link);
+            }
+            continue;
+        }
+
+        if let Some(parent) = link.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| HpkgError::Io(format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+        symlink(&target, &link)
+            .map_err(|e| HpkgError::Io(format!("Failed to link {}: {}", link.display(), e)))?;
+        report.linked.push(link);
+    }
+    Ok(report)
+}
+
+/// Removes every farm entry that goes through `<name>/active`, whichever version it came from,
+/// and returns them.
+fn unlink_farm(root: &Path, name: &str) -> Result<Vec<PathBuf>> {
+    let prefix = Path::new(name).join(ACTIVE_LINK);
+    let mut removed = Vec::new();
+    for dir in FARM_DIRS {
+        let farm_dir = root.join(dir);
+        let mut links = Vec::new();
+        walk_links(&farm_dir, &mut links)?;
+        for link in links {
+            let Ok(target) = std::fs::read_link(&link) else {
+                continue;
+            };
+            let relative: PathBuf = target.components().skip_while(|c| *c == Component::ParentDir).collect();
+            if relative.starts_with(&prefix) {
+                remove_link(&link)?;
+                remove_empty_parents(&link, &farm_dir);
+                removed.push(link);
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// The target of the farm entry `<root>/<entry>` exposing `path` of package `name`, relative to the
+/// directory the entry is in.
+fn farm_target(name: &str, entry: &Path, path: &Path) -> PathBuf {
+    let depth = entry.components().count().saturating_sub(1);
+    let mut target: PathBuf = std::iter::repeat_n(Component::ParentDir, depth).collect();
+    target.push(name);
+    target.push(ACTIVE_LINK);
+    target.push(path);
+    target
+}
+
+fn walk_links(dir: &Path, links: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(HpkgError::Io(format!("Failed to read {}: {}", dir.display(), e))),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            links.push(entry.path());
+        } else if file_type.is_dir() {
+            walk_links(&entry.path(), links)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_link(link: &Path) -> Result<()> {
+    match std::fs::remove_file(link) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(HpkgError::Io(format!("Failed to remove {}: {}", link.display(), e))),
+    }
+}
+
+/// Removes the directories between `path` and `farm_dir` that are now empty.
+fn remove_empty_parents(path: &Path, farm_dir: &Path) {
+    for dir in path.ancestors().skip(1).take_while(|d| *d != farm_dir) {
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "switching versions needs symlink support"))
+}
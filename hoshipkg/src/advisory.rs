@@ -0,0 +1,103 @@
+//! The local advisory database: the advisories each constellation published as of its last
+//! `hpkg sync`, checked by `hpkg audit` and before installing.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+pub use hoshi_core::{Advisory, AdvisoryList, Severity};
+
+use crate::error::{HpkgError, Result};
+use crate::source;
+
+/// One constellation's advisories as of its last sync.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncedAdvisories {
+    /// Unix seconds.
+    pub fetched_at: u64,
+    /// The location the advisories were fetched from.
+    pub source: String,
+    pub advisories: Vec<Advisory>,
+}
+
+/// An advisory that applies to a package, and the constellation that published it.
+#[derive(Debug, Clone, Serialize)]
+pub struct AdvisoryMatch<'a> {
+    pub constellation: &'a str,
+    #[serde(flatten)]
+    pub advisory: &'a Advisory,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AdvisoryDb {
+    /// Keyed by configured constellation name.
+    #[serde(default)]
+    constellations: BTreeMap<String, SyncedAdvisories>,
+}
+
+impl AdvisoryDb {
+    pub fn get_default_path() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("hoshi")
+            .join("advisories.json")
+    }
+
+    /// Loads the database, or an empty one if nothing has been synced yet.
+    pub async fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(AdvisoryDb::default());
+        }
+        let content = tokio::fs::read_to_string(path).await
+            .map_err(|e| HpkgError::Io(format!("Failed to read advisories {}: {}", path.display(), e)))?;
+        serde_json::from_str(&content)
+            .map_err(|e| HpkgError::Parse(format!("Advisory database {} is corrupted: {}", path.display(), e)))
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await
+                .map_err(|e| HpkgError::Io(format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        tokio::fs::write(path, content).await
+            .map_err(|e| HpkgError::Io(format!("Failed to write advisories {}: {}", path.display(), e)))
+    }
+
+    /// Replaces a constellation's advisories. `None` forgets them, for constellations that
+    /// stopped publishing any.
+    pub fn update(&mut self, constellation: &str, synced: Option<SyncedAdvisories>) {
+        match synced {
+            Some(synced) => self.constellations.insert(constellation.to_string(), synced),
+            None => self.constellations.remove(constellation),
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constellations.is_empty()
+    }
+
+    /// Names of the constellations with synced advisories, sorted.
+    pub fn constellations(&self) -> Vec<&str> {
+        self.constellations.keys().map(String::as_str).collect()
+    }
+
+    /// Advisories that affect `version` of `name`. With a constellation, only advisories that
+    /// constellation published count, since another constellation's package of the same name may
+    /// be different software.
+    pub fn affecting(&self, constellation: Option<&str>, name: &str, version: &str) -> Vec<AdvisoryMatch<'_>> {
+        self.constellations.iter()
+            .filter(|(publisher, _)| constellation.is_none_or(|c| c == publisher.as_str()))
+            .flat_map(|(publisher, synced)| synced.advisories.iter()
+                .filter(|a| a.affects(name, version))
+                .map(move |advisory| AdvisoryMatch { constellation: publisher, advisory }))
+            .collect()
+    }
+}
+
+/// Fetches and parses the advisories document at `location`.
+pub async fn fetch(location: &str) -> Result<AdvisoryList> {
+    let content = source::fetch_to_string(location).await?;
+    AdvisoryList::from_json(&content)
+        .map_err(|e| HpkgError::Parse(format!("Advisories from {}: {}", location, e)))
+}
@@ -0,0 +1,155 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::checksum;
+use crate::constellation::PackageMetadata;
+use crate::config::HoshiConfig;
+use crate::scheduler::DownloadScheduler;
+
+/// A cached archive, stored under `<root>/<name>/<version>/<checksum>/<file>`.
+#[derive(Debug)]
+pub struct CacheEntry {
+    pub name: String,
+    pub version: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+impl CacheEntry {
+    /// The `<root>/<name>/<version>/<checksum>` directory holding the archive.
+    pub fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or(&self.path)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageCache {
+    root: PathBuf,
+}
+
+impl PackageCache {
+    pub fn get_default_dir() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("hoshi")
+            .join("packages")
+    }
+
+    pub fn from_config(config: &HoshiConfig) -> Self {
+        PackageCache {
+            root: config.cache_dir.clone().unwrap_or_else(Self::get_default_dir),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Archives without a published checksum are cached under `unverified` and never reused.
+    pub fn entry_dir(&self, pkg: &PackageMetadata) -> PathBuf {
+        let key = pkg.checksum.as_deref().map_or("unverified".to_string(), |c| c.to_ascii_lowercase());
+        self.root.join(&pkg.name).join(&pkg.version).join(key)
+    }
+
+    pub fn archive_path(&self, pkg: &PackageMetadata) -> PathBuf {
+        self.entry_dir(pkg).join(DownloadScheduler::archive_file_name(pkg))
+    }
+
+    /// Cheap check for whether `lookup` may hit, without hashing the archive.
+    pub fn contains(&self, pkg: &PackageMetadata) -> bool {
+        pkg.checksum.is_some() && self.archive_path(pkg).exists()
+    }
+
+    /// Returns the cached archive if it is present and its digest still matches the metadata.
+    pub async fn lookup(&self, pkg: &PackageMetadata) -> Option<PathBuf> {
+        let expected = pkg.checksum.as_deref()?;
+        let path = self.archive_path(pkg);
+        if !path.exists() {
+            return None;
+        }
+
+        match checksum::sha256_file(&path).await {
+            Ok(actual) if actual.eq_ignore_ascii_case(expected) => Some(path),
+            _ => {
+                let _ = tokio::fs::remove_file(&path).await;
+                None
+            }
+        }
+    }
+
+    pub async fn entries(&self) -> io::Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        if !self.root.exists() {
+            return Ok(entries);
+        }
+
+        for name_dir in read_subdirs(&self.root).await? {
+            for version_dir in read_subdirs(&name_dir).await? {
+                for checksum_dir in read_subdirs(&version_dir).await? {
+                    let mut files = tokio::fs::read_dir(&checksum_dir).await?;
+                    while let Some(file) = files.next_entry().await? {
+                        let metadata = file.metadata().await?;
+                        if !metadata.is_file() {
+                            continue;
+                        }
+                        entries.push(CacheEntry {
+                            name: file_name_of(&name_dir),
+                            version: file_name_of(&version_dir),
+                            path: file.path(),
+                            size: metadata.len(),
+                            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Deletes an entry and any parent directories it leaves empty.
+    pub async fn remove(&self, entry: &CacheEntry) -> io::Result<()> {
+        tokio::fs::remove_dir_all(entry.dir()).await?;
+
+        let mut dir = entry.dir().parent();
+        while let Some(current) = dir {
+            if current == self.root || tokio::fs::remove_dir(current).await.is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+        Ok(())
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.2} {}", size, UNITS[unit])
+    }
+}
+
+async fn read_subdirs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut subdirs = Vec::new();
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            subdirs.push(entry.path());
+        }
+    }
+    Ok(subdirs)
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+}
@@ -0,0 +1,24 @@
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+
+/// Computes the lowercase hex SHA-256 digest of a file.
+pub async fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || sha256_file_blocking(&path)).await?
+}
+
+/// `sha256_file` for callers already on a blocking thread.
+pub fn sha256_file_blocking(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
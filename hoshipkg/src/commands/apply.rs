@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+
+use hoshipkg::advisory::AdvisoryDb;
+use hoshipkg::cache::PackageCache;
+use hoshipkg::config::HoshiConfig;
+use hoshipkg::constellation::{self, Constellation, PackageMetadata};
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::history::{History, HistoryEntry, PackageChange};
+use hoshipkg::plan::InstallPlan;
+use hoshipkg::registry::{InstallReason, InstalledPackage, PackageRegistry};
+use hoshipkg::resolver::{self, PackageRequest};
+use hoshipkg::scheduler::DownloadScheduler;
+use hoshipkg::snapshot;
+use hoshipkg::status;
+use hoshipkg::transaction::{self, FailedPackage, Transaction};
+use hoshipkg::ui::{self, Options};
+use hoshipkg::world::{WorldDiff, WorldFile};
+
+#[derive(Debug, Serialize)]
+struct ApplyReport<'a> {
+    world: &'a Path,
+    dry_run: bool,
+    plan: &'a InstallPlan,
+    /// Why the plan would not fit on disk; a dry run reports it instead of failing.
+    insufficient_space: Option<String>,
+    mark_explicit: &'a [String],
+    mark_dependency: &'a [String],
+    installed: Vec<InstalledPackage>,
+    removed: Vec<InstalledPackage>,
+    failed: Vec<FailedPackage>,
+    snapshot_id: Option<u64>,
+    history_id: Option<u64>,
+}
+
+/// Makes the registry match the world file at `world_path`, or the default one.
+pub async fn handle(world_path: Option<&Path>, jobs: Option<usize>, options: &Options) -> Result<()> {
+    let world_path: PathBuf = world_path.map_or_else(WorldFile::get_default_path, Path::to_path_buf);
+    let world = WorldFile::load(&world_path).await?;
+
+    let registry_path = PackageRegistry::get_install_path();
+    let mut registry = PackageRegistry::load(&registry_path).await?;
+    let mut diff = WorldDiff::new(&world, &registry);
+
+    let mut packages: Vec<PackageMetadata> = Vec::new();
+    if !diff.missing.is_empty() {
+        let constellations = Constellation::load_all().await?;
+        let available = constellation::sync_constellations(&constellations).await?;
+
+        status!("\nResolving dependencies...");
+        let requests: Vec<PackageRequest> = diff.missing.iter().map(|e| e.request()).collect();
+        packages = resolver::resolve_requests(&available, &constellations, &requests)?;
+        // Dependencies that are already installed in any version are left alone.
+        packages.retain(|p| diff.missing.iter().any(|e| e.name == p.name) || registry.versions_of(&p.name).is_empty());
+    }
+    diff.drop_replaced(&packages);
+    diff.keep_required(&world, &registry, &packages);
+
+    let config = HoshiConfig::load().await?;
+    let cache = PackageCache::from_config(&config);
+    let explicit: Vec<String> = world.packages.iter().map(|e| e.name.clone()).collect();
+    let removals = std::mem::take(&mut diff.removals);
+    let transaction = Transaction::new(packages, explicit.clone(), &registry, cache, Transaction::default_install_root())
+        .with_removals(removals);
+
+    let nothing_to_do = diff.is_empty() && transaction.plan().removals.is_empty();
+    status!("Applying {}:", world_path.display());
+    if nothing_to_do {
+        status!("Nothing to do, the installation matches the world file.");
+    } else if transaction.packages().is_empty() && transaction.plan().removals.is_empty() {
+        status!("\nChanges to apply:");
+    } else {
+        transaction.plan().print("Changes to apply:", true);
+    }
+    for name in &diff.mark_explicit {
+        status!(" - mark {} as explicitly installed", name);
+    }
+    for name in &diff.mark_dependency {
+        status!(" - keep {} as a dependency, it is no longer listed but still required", name);
+    }
+    config.license_policy.check(transaction.packages(), &explicit)?;
+    let advisories = AdvisoryDb::load(&AdvisoryDb::get_default_path()).await?;
+    config.advisory_policy.check(&advisories, transaction.packages())?;
+
+    let mut report = ApplyReport {
+        world: &world_path,
+        dry_run: options.dry_run,
+        plan: transaction.plan(),
+        insufficient_space: None,
+        mark_explicit: &diff.mark_explicit,
+        mark_dependency: &diff.mark_dependency,
+        installed: Vec::new(),
+        removed: Vec::new(),
+        failed: Vec::new(),
+        snapshot_id: None,
+        history_id: None,
+    };
+
+    let space = transaction.check_space(false);
+    if options.dry_run || nothing_to_do {
+        if options.dry_run {
+            if let Err(e) = &space {
+                status!("\n{}", e);
+                report.insufficient_space = Some(e.to_string());
+            }
+            status!("\nDry run, nothing was changed.");
+        }
+        if ui::is_json() {
+            ui::emit_json(&report);
+        }
+        return Ok(());
+    }
+
+    space?;
+    if !ui::confirm("Do you want to apply these changes?", options) {
+        return Err(HpkgError::UserAbort("Apply aborted by user.".to_string()));
+    }
+
+    let mut downloaded_packages = Vec::new();
+    if !transaction.packages().is_empty() {
+        status!("\nStarting package downloads...");
+        let scheduler = DownloadScheduler::new(jobs.unwrap_or(config.max_parallel_downloads));
+        downloaded_packages = transaction.download(&scheduler).await?;
+    }
+
+    let affected = transaction.affected_paths(&registry).await?;
+    if let Some(snapshot) = snapshot::take(&config, &registry_path, &affected).await? {
+        status!("\nSnapshot {} taken.", snapshot.id);
+        report.snapshot_id = Some(snapshot.id);
+    }
+
+    let outcome = transaction.install(&downloaded_packages, &mut registry).await?;
+    let mut changes = transaction.changes(&outcome);
+
+    report.removed = transaction.uninstall_removals(&mut registry, &outcome).await?;
+    changes.extend(report.removed.iter().map(PackageChange::removed));
+    for name in &diff.mark_explicit {
+        registry.set_reason(name, InstallReason::Explicit);
+    }
+    for name in &diff.mark_dependency {
+        registry.set_reason(name, InstallReason::Dependency);
+    }
+
+    registry.save(&registry_path).await?;
+    status!("Package registry updated.");
+
+    let history = History::open(&History::get_default_path());
+    report.history_id = history.append(HistoryEntry::new(changes)).await?;
+    if let Some(id) = report.history_id {
+        status!("Recorded as transaction {}.", id);
+    }
+
+    report.installed = outcome.installed;
+    report.failed = outcome.failed;
+    if report.failed.is_empty() {
+        status!("\nApply complete!");
+    }
+    if ui::is_json() {
+        ui::emit_json(&report);
+    }
+    transaction::ensure_installed(&report.failed, "The installation does not match the world file")
+}
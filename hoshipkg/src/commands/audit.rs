@@ -0,0 +1,75 @@
+use serde::Serialize;
+
+use hoshipkg::advisory::{AdvisoryDb, AdvisoryMatch};
+use hoshipkg::config::HoshiConfig;
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::status;
+use hoshipkg::ui;
+use hoshipkg::version;
+
+#[derive(Debug, Serialize)]
+struct Finding<'a> {
+    name: &'a str,
+    version: &'a str,
+    /// Where the installed version came from; `None` when that wasn't recorded.
+    constellation: Option<&'a str>,
+    advisories: Vec<AdvisoryMatch<'a>>,
+}
+
+/// Reports installed packages affected by synced advisories. Fails with `Advisory` when any are,
+/// so scripts can act on the exit code.
+pub async fn handle() -> Result<()> {
+    let config = HoshiConfig::load().await?;
+    let registry = PackageRegistry::load(&PackageRegistry::get_install_path()).await?;
+    let advisories = AdvisoryDb::load(&AdvisoryDb::get_default_path()).await?;
+
+    if advisories.is_empty() {
+        status!("No advisories have been synced. Run 'hpkg sync <constellation>' for constellations that publish them.");
+    } else {
+        status!("Auditing against advisories from: {}", advisories.constellations().join(", "));
+    }
+
+    let mut packages = registry.list_packages();
+    packages.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| version::compare_versions(&a.version, &b.version)));
+
+    let mut findings = Vec::new();
+    for package in packages {
+        let constellation = package.source.as_ref().map(|s| s.constellation.as_str());
+        let matches = config.advisory_policy.affecting(&advisories, constellation, &package.name, &package.version);
+        if !matches.is_empty() {
+            findings.push(Finding { name: &package.name, version: &package.version, constellation, advisories: matches });
+        }
+    }
+
+    for finding in &findings {
+        status!("\n{} v{}:", finding.name, finding.version);
+        for m in &finding.advisories {
+            let affected: Vec<String> = m.advisory.affected.iter().map(|req| req.to_string()).collect();
+            status!("  {} ({}) from {}, affects {}", m.advisory.id, m.advisory.severity, m.constellation, affected.join(" or "));
+            status!("    {}", m.advisory.description);
+        }
+    }
+
+    let advisory_count: usize = findings.iter().map(|f| f.advisories.len()).sum();
+    if findings.is_empty() {
+        status!("\nNo installed package has a known advisory.");
+    } else {
+        status!("\n{} advisories affect {} installed packages.", advisory_count, findings.len());
+    }
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "constellations": advisories.constellations(),
+            "findings": findings,
+        }));
+    }
+
+    if !findings.is_empty() {
+        return Err(HpkgError::Advisory(format!(
+            "{} installed packages have known advisories.",
+            findings.len()
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::time::{Duration, SystemTime};
+use serde::Serialize;
+
+use hoshipkg::cache::{self, PackageCache};
+use hoshipkg::config::HoshiConfig;
+use hoshipkg::error::Result;
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::status;
+use hoshipkg::ui::{self, Options};
+
+#[derive(Debug, Serialize)]
+struct RemovedArchive {
+    name: String,
+    version: String,
+    bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct CleanReport {
+    cache_dir: String,
+    dry_run: bool,
+    archives: usize,
+    total_bytes: u64,
+    removed: Vec<RemovedArchive>,
+    freed_bytes: u64,
+}
+
+pub async fn handle(all: bool, uninstalled: bool, older_than_days: Option<u64>, options: &Options) -> Result<()> {
+    let config = HoshiConfig::load().await?;
+    let cache = PackageCache::from_config(&config);
+
+    let entries = cache.entries().await?;
+    let total_size: u64 = entries.iter().map(|e| e.size).sum();
+    status!("Package cache at {}: {} archives, {}", cache.root().display(), entries.len(), cache::format_size(total_size));
+
+    let mut report = CleanReport {
+        cache_dir: cache.root().display().to_string(),
+        dry_run: options.dry_run,
+        archives: entries.len(),
+        total_bytes: total_size,
+        removed: Vec::new(),
+        freed_bytes: 0,
+    };
+
+    if !all && !uninstalled && older_than_days.is_none() {
+        status!("Nothing to clean. Use --all, --uninstalled or --older-than <DAYS> to prune the cache.");
+        if ui::is_json() {
+            ui::emit_json(&report);
+        }
+        return Ok(());
+    }
+
+    let registry_path = PackageRegistry::get_install_path();
+    let registry = PackageRegistry::load(&registry_path).await?;
+    let installed = registry.list_packages();
+
+    let cutoff = older_than_days
+        .map(|days| SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60));
+
+    for entry in &entries {
+        let is_installed = installed.iter().any(|p| p.name == entry.name && p.version == entry.version);
+        let is_old = cutoff.is_some_and(|cutoff| entry.modified < cutoff);
+
+        if !(all || (uninstalled && !is_installed) || is_old) {
+            continue;
+        }
+
+        if options.dry_run {
+            status!("Would remove {} v{} ({})", entry.name, entry.version, cache::format_size(entry.size));
+        } else if let Err(e) = cache.remove(entry).await {
+            eprintln!("Error removing {}: {}", entry.path.display(), e);
+            continue;
+        } else {
+            status!("Removed {} v{} ({})", entry.name, entry.version, cache::format_size(entry.size));
+        }
+
+        report.freed_bytes += entry.size;
+        report.removed.push(RemovedArchive {
+            name: entry.name.clone(),
+            version: entry.version.clone(),
+            bytes: entry.size,
+        });
+    }
+
+    if options.dry_run {
+        status!("\nWould remove {} archives, freeing {}.", report.removed.len(), cache::format_size(report.freed_bytes));
+        status!("Dry run, nothing was changed.");
+    } else {
+        status!("\nRemoved {} archives, freed {}.", report.removed.len(), cache::format_size(report.freed_bytes));
+        status!("Package cache size is now {}.", cache::format_size(total_size - report.freed_bytes));
+    }
+
+    if ui::is_json() {
+        ui::emit_json(&report);
+    }
+    Ok(())
+}
@@ -1,17 +1,78 @@
-use crate::registry::PackageRegistry;
+use hoshipkg::activation;
+use hoshipkg::config::HoshiConfig;
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::history::{History, HistoryEntry, PackageChange};
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::snapshot;
+use hoshipkg::status;
+use hoshipkg::transaction;
+use hoshipkg::ui::{self, Options};
 
-pub async fn handle(package_name: &str) {
+/// Uninstalls `version` of a package, deleting its install directory. The version may be left out
+/// when only one is installed.
+pub async fn handle(package_name: &str, version: Option<&str>, options: &Options) -> Result<()> {
     let registry_path = PackageRegistry::get_install_path();
-    let mut registry = PackageRegistry::load(&registry_path).await;
+    let mut registry = PackageRegistry::load(&registry_path).await?;
+    let version = select_version(&registry, package_name, version)?;
 
-    match registry.remove(package_name, None) {
-        Some(pkg) => {
-            println!("Successfully removed package: {} v{} from registry.", pkg.name, pkg.version);
-            registry.save(&registry_path).await;
-            println!("Package registry updated.");
-        },
-        None => {
-            println!("Package '{}' not found in registry.", package_name);
+    let pkg = activation::find_installed(&registry, package_name, &version)?;
+    if options.dry_run {
+        status!("Would remove package: {} v{} and delete {}.", pkg.name, pkg.version, pkg.install_path.display());
+        status!("Dry run, nothing was changed.");
+        if ui::is_json() {
+            ui::emit_json(&serde_json::json!({ "dry_run": true, "removed": [pkg] }));
         }
+        return Ok(());
+    }
+
+    let config = HoshiConfig::load().await?;
+    if let Some(snapshot) = snapshot::take(&config, &registry_path, std::slice::from_ref(&pkg.install_path)).await? {
+        status!("Snapshot {} taken.", snapshot.id);
+    }
+
+    let (pkg, links) = transaction::uninstall(&mut registry, package_name, &version).await?
+        .ok_or_else(|| HpkgError::NotFound(format!("Package '{}' not found in registry.", package_name)))?;
+    status!("Successfully removed package: {} v{}.", pkg.name, pkg.version);
+    for link in &links.unlinked {
+        status!(" - removed link {}", link.display());
+    }
+    if let Some(active) = registry.active_version(&pkg.name) {
+        status!("{} v{} is now active.", pkg.name, active);
+    }
+    registry.save(&registry_path).await?;
+    status!("Package registry updated.");
+
+    let history = History::open(&History::get_default_path());
+    let history_id = history.append(HistoryEntry::new(vec![PackageChange::removed(&pkg)])).await?;
+    if let Some(id) = history_id {
+        status!("Recorded as transaction {}.", id);
+    }
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "dry_run": false,
+            "removed": [pkg],
+            "unlinked": links.unlinked,
+            "history_id": history_id,
+        }));
+    }
+    Ok(())
+}
+
+/// The installed version to delete: `version` if given and installed, otherwise the only one.
+fn select_version(registry: &PackageRegistry, name: &str, version: Option<&str>) -> Result<String> {
+    if let Some(version) = version {
+        return Ok(activation::find_installed(registry, name, version)?.version.clone());
+    }
+    match registry.versions_of(name).as_slice() {
+        [] => Err(HpkgError::NotFound(format!("Package '{}' not found in registry.", name))),
+        [only] => Ok(only.version.clone()),
+        several => {
+            let versions: Vec<&str> = several.iter().map(|p| p.version.as_str()).collect();
+            Err(HpkgError::Conflict(format!(
+                "{} has several installed versions ({}); say which one to delete, e.g. 'hpkg delete {} {}'.",
+                name, versions.join(", "), name, versions[0]
+            )))
+        },
     }
 }
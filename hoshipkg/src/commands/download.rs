@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+use hoshipkg::cache::PackageCache;
+use hoshipkg::constellation::{self, Constellation, ConstellationMetadata, PackageMetadata};
+use hoshipkg::config::HoshiConfig;
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::resolver;
+use hoshipkg::scheduler::{DownloadScheduler, DownloadedPackage};
+use hoshipkg::status;
+use hoshipkg::ui::{self, Options};
+
+fn constellation_dir_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
+
+/// Resolves the packages and their dependencies, then writes the archives and a trimmed copy of each
+/// source constellation's metadata to `dest` so it can be used as a `file://` constellation offline.
+pub async fn handle(package_specs: &[String], dest: &Path, jobs: Option<usize>, options: &Options) -> Result<()> {
+    let constellations = Constellation::load_all().await?;
+    let all_available_packages = constellation::sync_constellations(&constellations).await?;
+
+    status!("\nResolving dependencies...");
+    let specs: Vec<&str> = package_specs.iter().map(String::as_str).collect();
+    let packages = resolver::resolve(&all_available_packages, &constellations, &specs)?;
+
+    status!("\nPackages to download:");
+    for pkg in &packages {
+        status!(" - {} v{} from {} ({} MB)", pkg.name, pkg.version, pkg.constellation, pkg.size_mb);
+    }
+
+    if options.dry_run {
+        status!("\nDry run, nothing would be written to {}.", dest.display());
+        if ui::is_json() {
+            ui::emit_json(&serde_json::json!({
+                "dry_run": true,
+                "dest": dest,
+                "packages": packages,
+            }));
+        }
+        return Ok(());
+    }
+
+    status!("\nStarting package downloads...");
+    let config = HoshiConfig::load().await?;
+    let cache = PackageCache::from_config(&config);
+    let scheduler = DownloadScheduler::new(jobs.unwrap_or(config.max_parallel_downloads));
+    let downloaded_packages = scheduler.download_all(&packages, &cache).await?;
+    let (dest, config_path) = write_bundle(&downloaded_packages, &constellations, dest).await?;
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "dry_run": false,
+            "dest": dest,
+            "packages": packages,
+            "downloaded": downloaded_packages,
+            "constellation_config": config_path,
+        }));
+    }
+    Ok(())
+}
+
+/// Copies the downloaded archives into `dest`, next to a trimmed copy of each source constellation's
+/// metadata and a `constellations.json` pointing at them. Every location is relative to the bundle,
+/// so it can be mounted anywhere. Returns the absolute `dest` and the path of that config.
+pub async fn write_bundle(
+    downloaded_packages: &[DownloadedPackage],
+    constellations: &[Constellation],
+    dest: &Path,
+) -> Result<(PathBuf, PathBuf)> {
+    let dest = std::path::absolute(dest)?;
+    let mut bundled: Vec<(String, Vec<PackageMetadata>)> = Vec::new();
+
+    for downloaded in downloaded_packages {
+        let pkg = &downloaded.package;
+        let file_name = DownloadScheduler::archive_file_name(pkg);
+        let packages_dir = dest.join(constellation_dir_name(&pkg.constellation)).join("packages");
+
+        tokio::fs::create_dir_all(&packages_dir).await
+            .map_err(|e| HpkgError::Io(format!("Failed to create {}: {}", packages_dir.display(), e)))?;
+        tokio::fs::copy(&downloaded.path, packages_dir.join(&file_name)).await
+            .map_err(|e| HpkgError::Io(format!("Failed to copy {} into {}: {}", file_name, packages_dir.display(), e)))?;
+        status!("Saved {} v{} (served by {})", pkg.name, pkg.version, downloaded.served_by);
+
+        let mut bundled_pkg = pkg.clone();
+        bundled_pkg.download_url = format!("packages/{}", file_name);
+        match bundled.iter_mut().find(|(name, _)| *name == pkg.constellation) {
+            Some((_, pkgs)) => pkgs.push(bundled_pkg),
+            None => bundled.push((pkg.constellation.clone(), vec![bundled_pkg])),
+        }
+    }
+
+    let mut offline_constellations = Vec::new();
+    for (name, pkgs) in bundled {
+        let metadata_location = format!("{}/constellation.json", constellation_dir_name(&name));
+        let metadata_path = dest.join(&metadata_location);
+        let meta = ConstellationMetadata::new(name.clone(), format!("Offline bundle of {}", name), pkgs);
+        let content = serde_json::to_string_pretty(&meta)?;
+        tokio::fs::write(&metadata_path, content).await
+            .map_err(|e| HpkgError::Io(format!("Failed to write {}: {}", metadata_path.display(), e)))?;
+
+        let priority = constellations.iter().find(|c| c.name == name).map_or(0, |c| c.priority);
+        offline_constellations.push(Constellation {
+            name,
+            metadata_url: metadata_location,
+            mirrors: Vec::new(),
+            rank_mirrors: false,
+            priority,
+        });
+    }
+
+    let config_path = dest.join("constellations.json");
+    let content = serde_json::to_string_pretty(&offline_constellations)?;
+    tokio::fs::write(&config_path, content).await
+        .map_err(|e| HpkgError::Io(format!("Failed to write {}: {}", config_path.display(), e)))?;
+
+    status!("\nDownloaded {} packages to {}.", downloaded_packages.len(), dest.display());
+    status!(
+        "On the offline host, symlink {} to <bundle>/constellations.json wherever the bundle is mounted.",
+        Constellation::get_config_path().display()
+    );
+    Ok((dest, config_path))
+}
@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+use clap::ValueEnum;
+
+use hoshipkg::activation;
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::ui;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// Guesses from `$SHELL`, falling back to bash.
+    fn detect() -> Self {
+        let shell = std::env::var("SHELL").unwrap_or_default();
+        match Path::new(&shell).file_name().and_then(|n| n.to_str()) {
+            Some("zsh") => Shell::Zsh,
+            Some("fish") => Shell::Fish,
+            _ => Shell::Bash,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+
+    /// Single-quotes `value` so the shell takes it literally.
+    fn quote(self, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("'{}'", value.replace('\'', "'\\''")),
+            Shell::Fish => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+        }
+    }
+
+    fn snippet(self, bin_dir: &Path) -> String {
+        let dir = self.quote(&bin_dir.display().to_string());
+        match self {
+            Shell::Bash | Shell::Zsh => format!(
+                "# Add to ~/.{name}rc: eval \"$(hpkg env --shell {name})\"\n\
+                 case \":$PATH:\" in\n    *:{dir}:*) ;;\n    *) export PATH={dir}\"${{PATH:+:$PATH}}\" ;;\nesac\n",
+                name = self.name(),
+            ),
+            Shell::Fish => format!(
+                "# Add to ~/.config/fish/config.fish: hpkg env --shell fish | source\n\
+                 contains -- {dir} $PATH; or set -gx PATH {dir} $PATH\n",
+            ),
+        }
+    }
+}
+
+/// Prints the shell code that puts the shared bin directory of the install root on the PATH. The
+/// root is the one the installed packages live under, so the output doesn't depend on the
+/// directory the shell starts in.
+pub async fn handle(shell: Option<Shell>) -> Result<()> {
+    let shell = shell.unwrap_or_else(Shell::detect);
+    let registry = PackageRegistry::load(&PackageRegistry::get_install_path()).await?;
+    let packages = registry.list_packages();
+    let root = packages.iter()
+        .find(|p| registry.is_active(p))
+        .or_else(|| packages.first())
+        .and_then(|p| activation::install_root_of(p))
+        .ok_or_else(|| HpkgError::NotFound(
+            "No packages are installed, so there is no bin directory to put on the PATH yet.".to_string()
+        ))?;
+    let bin_dir: PathBuf = root.join(activation::FARM_DIRS[0]);
+    let snippet = shell.snippet(&bin_dir);
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "shell": shell.name(),
+            "bin_dir": bin_dir,
+            "snippet": snippet,
+        }));
+        return Ok(());
+    }
+    print!("{}", snippet);
+    Ok(())
+}
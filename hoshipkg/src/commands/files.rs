@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+use serde::Serialize;
+
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::ui;
+
+#[derive(Debug, Serialize)]
+struct PackageFiles<'a> {
+    name: &'a str,
+    version: &'a str,
+    files: Vec<PathBuf>,
+}
+
+pub async fn handle(package_name: &str) -> Result<()> {
+    let registry_path = PackageRegistry::get_install_path();
+    let registry = PackageRegistry::load(&registry_path).await?;
+
+    let installed = registry.versions_of(package_name);
+    if installed.is_empty() {
+        return Err(HpkgError::NotFound(format!("Package '{}' is not installed.", package_name)));
+    }
+
+    let listings: Vec<PackageFiles> = installed.iter()
+        .map(|pkg| PackageFiles {
+            name: &pkg.name,
+            version: &pkg.version,
+            files: pkg.file_paths().collect(),
+        })
+        .collect();
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({ "packages": listings }));
+        return Ok(());
+    }
+
+    for listing in listings {
+        if listing.files.is_empty() {
+            eprintln!("{} v{} has no recorded files (installed before file lists were kept).", listing.name, listing.version);
+        }
+        for file in listing.files {
+            println!("{} {}", listing.name, file.display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use hoshipkg::error::Result;
+use hoshipkg::history::History;
+use hoshipkg::timestamp;
+use hoshipkg::ui;
+
+pub async fn handle(id: Option<u64>) -> Result<()> {
+    let history = History::open(&History::get_default_path());
+
+    if let Some(id) = id {
+        let entry = history.get(id).await?;
+        if ui::is_json() {
+            ui::emit_json(&entry);
+            return Ok(());
+        }
+
+        println!("Transaction {}", entry.id);
+        println!("  Date:    {}", timestamp::format_utc(entry.timestamp));
+        println!("  User:    {}", entry.user);
+        println!("  Command: {}", entry.command);
+        println!("  Changes:");
+        for change in &entry.changes {
+            println!("    {}", change);
+        }
+        return Ok(());
+    }
+
+    let entries = history.entries().await?;
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({ "transactions": entries }));
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("No transactions recorded.");
+        return Ok(());
+    }
+    for entry in entries.iter().rev() {
+        let changes: Vec<String> = entry.changes.iter().map(|c| c.to_string()).collect();
+        println!("{:>4}  {}  {:<10} {}", entry.id, timestamp::format_utc(entry.timestamp), entry.user, entry.command);
+        println!("      {}", changes.join(", "));
+    }
+    Ok(())
+}
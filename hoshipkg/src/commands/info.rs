@@ -0,0 +1,109 @@
+use serde::Serialize;
+
+use hoshipkg::constellation::{self, Constellation, PackageMetadata};
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::registry::{InstallReason, InstalledPackage, PackageRegistry};
+use hoshipkg::status;
+use hoshipkg::timestamp;
+use hoshipkg::ui;
+use hoshipkg::version;
+
+#[derive(Debug, Serialize)]
+struct AvailableVersion<'a> {
+    constellation: &'a str,
+    #[serde(flatten)]
+    package: &'a PackageMetadata,
+}
+
+#[derive(Debug, Serialize)]
+struct InfoReport<'a> {
+    name: &'a str,
+    installed: Vec<&'a InstalledPackage>,
+    available: Vec<AvailableVersion<'a>>,
+}
+
+pub async fn handle(package_name: &str) -> Result<()> {
+    let registry_path = PackageRegistry::get_install_path();
+    let registry = PackageRegistry::load(&registry_path).await?;
+
+    // Installed packages can still be described when every constellation is unreachable.
+    let constellations = Constellation::load_all().await?;
+    let all_available_packages = match constellation::sync_constellations(&constellations).await {
+        Ok(packages) => packages,
+        Err(e) => {
+            eprintln!("Warning: could not sync any constellation, showing installed information only: {}", e);
+            Vec::new()
+        }
+    };
+
+    let mut available: Vec<&PackageMetadata> = all_available_packages.iter()
+        .filter(|p| p.name == package_name)
+        .collect();
+    available.sort_by(|a, b| {
+        version::compare_versions(&b.version, &a.version)
+            .then_with(|| a.constellation.cmp(&b.constellation))
+    });
+    let installed = registry.versions_of(package_name);
+
+    if available.is_empty() && installed.is_empty() {
+        return Err(HpkgError::NotFound(format!(
+            "Package '{}' is neither installed nor available in any constellation.",
+            package_name
+        )));
+    }
+
+    if ui::is_json() {
+        ui::emit_json(&InfoReport {
+            name: package_name,
+            installed,
+            available: available.into_iter()
+                .map(|package| AvailableVersion { constellation: &package.constellation, package })
+                .collect(),
+        });
+        return Ok(());
+    }
+
+    status!("\nPackage: {}", package_name);
+
+    if installed.is_empty() {
+        status!("Installed: no");
+    } else {
+        status!("Installed:");
+        for pkg in &installed {
+            let reason = match pkg.reason {
+                InstallReason::Explicit => "explicitly installed",
+                InstallReason::Dependency => "installed as a dependency",
+            };
+            let date = pkg.installed_at.map_or("unknown".to_string(), timestamp::format_utc);
+            status!("  v{}", pkg.version);
+            status!("    Path:         {}", pkg.install_path.display());
+            status!("    Install date: {}", date);
+            status!("    Reason:       {}", reason);
+            status!("    License:      {}", pkg.license.as_deref().unwrap_or("not published"));
+        }
+    }
+
+    if available.is_empty() {
+        status!("Available: not provided by any configured constellation");
+        return Ok(());
+    }
+
+    status!("Available:");
+    for pkg in available {
+        let is_installed = installed.iter().any(|p| version::compare_versions(&p.version, &pkg.version).is_eq());
+        status!("  v{} from {}{}", pkg.version, pkg.constellation, if is_installed { " [installed]" } else { "" });
+        status!("    Description:    {}", pkg.description);
+        status!("    License:        {}", pkg.license.as_deref().unwrap_or("not published"));
+        status!("    Download URL:   {}", pkg.download_url);
+        for mirror in pkg.mirror_urls.iter().skip(1) {
+            status!("    Mirror:         {}", mirror);
+        }
+        status!("    Archive type:   {}", pkg.archive_type);
+        status!("    Download size:  {} MB", pkg.size_mb);
+        status!("    Installed size: {} MB", pkg.installed_size_mb.unwrap_or(pkg.size_mb));
+        let dependencies = pkg.dependencies.as_deref().unwrap_or_default();
+        status!("    Dependencies:   {}", if dependencies.is_empty() { "none".to_string() } else { dependencies.join(", ") });
+        status!("    Checksum:       {}", pkg.checksum.as_deref().unwrap_or("none published"));
+    }
+    Ok(())
+}
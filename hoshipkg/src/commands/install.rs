@@ -0,0 +1,140 @@
+use std::path::Path;
+use serde::Serialize;
+
+use hoshipkg::cache::PackageCache;
+use hoshipkg::config::HoshiConfig;
+use hoshipkg::constellation::PackageMetadata;
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::history::{History, HistoryEntry, PackageChange};
+use hoshipkg::lockfile::{LockedPackage, Lockfile};
+use hoshipkg::plan::{InstallPlan, PlanRemoval};
+use hoshipkg::registry::{InstallReason, InstalledPackage, PackageRegistry};
+use hoshipkg::scheduler::DownloadScheduler;
+use hoshipkg::snapshot;
+use hoshipkg::status;
+use hoshipkg::transaction::{self, FailedPackage, Transaction};
+use hoshipkg::ui::{self, Options};
+use hoshipkg::version;
+
+#[derive(Debug, Serialize)]
+struct InstallReport<'a> {
+    lockfile: &'a Path,
+    dry_run: bool,
+    plan: &'a InstallPlan,
+    installed: Vec<InstalledPackage>,
+    removed: Vec<InstalledPackage>,
+    failed: Vec<FailedPackage>,
+    snapshot_id: Option<u64>,
+    history_id: Option<u64>,
+}
+
+/// Makes the installation exactly match the lockfile at `lock_path`: every locked archive is
+/// installed from its recorded URL and checked against its recorded checksum, and everything else
+/// is removed. Nothing changes if any archive cannot be fetched or fails verification, and a
+/// package whose locked version fails to install keeps the versions it had.
+pub async fn handle_locked(lock_path: &Path, jobs: Option<usize>, options: &Options) -> Result<()> {
+    let lockfile = Lockfile::load(lock_path).await?;
+    let registry_path = PackageRegistry::get_install_path();
+    let mut registry = PackageRegistry::load(&registry_path).await?;
+
+    let packages: Vec<PackageMetadata> = lockfile.packages.iter()
+        .filter(|locked| !registry.versions_of(&locked.name).iter().any(|p| locked.matches(p)))
+        .map(LockedPackage::to_metadata)
+        .collect();
+    let mut installed = registry.list_packages();
+    installed.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| version::compare_versions(&a.version, &b.version)));
+    let removals: Vec<PlanRemoval> = installed.into_iter()
+        .filter(|p| lockfile.get(&p.name, &p.version).is_none())
+        .map(|p| PlanRemoval { name: p.name.clone(), version: p.version.clone() })
+        .collect();
+
+    let config = HoshiConfig::load().await?;
+    let cache = PackageCache::from_config(&config);
+    let explicit = lockfile.packages.iter()
+        .filter(|p| p.reason == InstallReason::Explicit)
+        .map(|p| p.name.clone())
+        .collect();
+    let transaction = Transaction::new(packages, explicit, &registry, cache, Transaction::default_install_root())
+        .with_removals(removals);
+
+    status!("Installing from {}:", lock_path.display());
+    let nothing_to_do = transaction.packages().is_empty() && transaction.plan().removals.is_empty();
+    if nothing_to_do {
+        status!("Nothing to do, the installation matches the lockfile.");
+    } else {
+        transaction.plan().print("Packages to install:", false);
+    }
+
+    let mut report = InstallReport {
+        lockfile: lock_path,
+        dry_run: options.dry_run,
+        plan: transaction.plan(),
+        installed: Vec::new(),
+        removed: Vec::new(),
+        failed: Vec::new(),
+        snapshot_id: None,
+        history_id: None,
+    };
+
+    if options.dry_run || nothing_to_do {
+        if options.dry_run {
+            status!("\nDry run, nothing was changed.");
+        }
+        if ui::is_json() {
+            ui::emit_json(&report);
+        }
+        return Ok(());
+    }
+
+    if !ui::confirm("Do you want to install the locked packages?", options) {
+        return Err(HpkgError::UserAbort("Install aborted by user.".to_string()));
+    }
+
+    // Every archive is fetched and verified before anything is installed or removed.
+    let mut downloaded_packages = Vec::new();
+    if !transaction.packages().is_empty() {
+        status!("\nStarting package downloads...");
+        let scheduler = DownloadScheduler::new(jobs.unwrap_or(config.max_parallel_downloads));
+        downloaded_packages = match transaction.download(&scheduler).await {
+            Ok(downloaded) => downloaded,
+            Err(e) => {
+                status!("\nA locked archive is unavailable or does not match its checksum; nothing was changed.");
+                return Err(e);
+            },
+        };
+    }
+
+    let affected = transaction.affected_paths(&registry).await?;
+    if let Some(snapshot) = snapshot::take(&config, &registry_path, &affected).await? {
+        status!("\nSnapshot {} taken.", snapshot.id);
+        report.snapshot_id = Some(snapshot.id);
+    }
+
+    let outcome = transaction.install(&downloaded_packages, &mut registry).await?;
+    let mut changes = transaction.changes(&outcome);
+
+    report.removed = transaction.uninstall_removals(&mut registry, &outcome).await?;
+    changes.extend(report.removed.iter().map(PackageChange::removed));
+    for locked in &lockfile.packages {
+        registry.set_reason(&locked.name, locked.reason);
+    }
+
+    registry.save(&registry_path).await?;
+    status!("Package registry updated.");
+
+    let history = History::open(&History::get_default_path());
+    report.history_id = history.append(HistoryEntry::new(changes)).await?;
+    if let Some(id) = report.history_id {
+        status!("Recorded as transaction {}.", id);
+    }
+
+    report.installed = outcome.installed;
+    report.failed = outcome.failed;
+    if report.failed.is_empty() {
+        status!("\nInstall complete!");
+    }
+    if ui::is_json() {
+        ui::emit_json(&report);
+    }
+    transaction::ensure_installed(&report.failed, "The installation does not match the lockfile")
+}
@@ -1,16 +1,40 @@
-use crate::registry::PackageRegistry;
+use serde::Serialize;
 
-pub async fn handle() {
+use hoshipkg::error::Result;
+use hoshipkg::registry::{InstalledPackage, PackageRegistry};
+use hoshipkg::ui;
+use hoshipkg::version;
+
+#[derive(Serialize)]
+struct ListedPackage<'a> {
+    #[serde(flatten)]
+    package: &'a InstalledPackage,
+    active: bool,
+}
+
+pub async fn handle() -> Result<()> {
     let registry_path = PackageRegistry::get_install_path();
-    let registry = PackageRegistry::load(&registry_path).await;
+    let registry = PackageRegistry::load(&registry_path).await?;
+
+    let mut packages = registry.list_packages();
+    packages.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| version::compare_versions(&a.version, &b.version)));
+
+    if ui::is_json() {
+        let packages: Vec<ListedPackage> = packages.into_iter()
+            .map(|package| ListedPackage { package, active: registry.is_active(package) })
+            .collect();
+        ui::emit_json(&serde_json::json!({ "packages": packages }));
+        return Ok(());
+    }
 
-    let packages = registry.list_packages();
     if packages.is_empty() {
         println!("No packages installed.");
     } else {
         println!("Installed packages:");
         for pkg in packages {
-            println!(" - {} v{} installed at {}", pkg.name, pkg.version, pkg.install_path.display());
+            let active = if registry.is_active(pkg) { " (active)" } else { "" };
+            println!(" - {} v{}{} installed at {}", pkg.name, pkg.version, active, pkg.install_path.display());
         }
     }
+    Ok(())
 }
@@ -0,0 +1,50 @@
+use std::path::{Path, PathBuf};
+
+use hoshipkg::cache::PackageCache;
+use hoshipkg::config::HoshiConfig;
+use hoshipkg::constellation::{self, Constellation};
+use hoshipkg::error::Result;
+use hoshipkg::lockfile::Lockfile;
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::status;
+use hoshipkg::ui::{self, Options};
+
+/// Writes every installed package to a lockfile at `lock_path`, or `hoshi.lock`.
+pub async fn handle(lock_path: Option<&Path>, options: &Options) -> Result<()> {
+    let lock_path: PathBuf = lock_path.map_or_else(Lockfile::get_default_path, Path::to_path_buf);
+    let registry_path = PackageRegistry::get_install_path();
+    let registry = PackageRegistry::load(&registry_path).await?;
+
+    // Only entries installed before sources were recorded need the constellation metadata.
+    let (constellations, available) = if registry.list_packages().iter().any(|p| p.source.is_none()) {
+        let constellations = Constellation::load_all().await?;
+        let available = constellation::sync_constellations(&constellations).await?;
+        (constellations, available)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    let config = HoshiConfig::load().await?;
+    let cache = PackageCache::from_config(&config);
+    let lockfile = Lockfile::from_registry(&registry, &available, &constellations, &cache).await?;
+
+    for package in &lockfile.packages {
+        status!(" - {} v{} from {} ({})", package.name, package.version, package.constellation, package.download_url);
+    }
+
+    if options.dry_run {
+        status!("\nWould lock {} packages to {}.", lockfile.packages.len(), lock_path.display());
+        status!("Dry run, nothing was changed.");
+    } else {
+        lockfile.save(&lock_path).await?;
+        status!("\nLocked {} packages to {}.", lockfile.packages.len(), lock_path.display());
+    }
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "dry_run": options.dry_run,
+            "path": lock_path,
+            "lockfile": lockfile,
+        }));
+    }
+    Ok(())
+}
@@ -1,4 +1,22 @@
+pub mod apply;
+pub mod audit;
 pub mod merge;
 pub mod delete;
 pub mod list;
 pub mod sync;
+pub mod undo;
+pub mod verify;
+pub mod why;
+pub mod why_not;
+pub mod clean;
+pub mod download;
+pub mod env;
+pub mod files;
+pub mod history;
+pub mod info;
+pub mod install;
+pub mod lock;
+pub mod owns;
+pub mod sbom;
+pub mod snapshot;
+pub mod use_version;
@@ -0,0 +1,39 @@
+use std::path::{Path, PathBuf};
+
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::ui;
+
+/// Makes `path` absolute the way install paths were recorded: the parent directory is canonicalized,
+/// the final component is kept as-is so a symlink is looked up rather than its target.
+fn normalize(path: &Path) -> std::io::Result<PathBuf> {
+    let absolute = std::path::absolute(path)?;
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => match parent.canonicalize() {
+            Ok(parent) => Ok(parent.join(name)),
+            Err(_) => Ok(absolute),
+        },
+        _ => Ok(absolute),
+    }
+}
+
+pub async fn handle(path: &Path) -> Result<()> {
+    let registry_path = PackageRegistry::get_install_path();
+    let registry = PackageRegistry::load(&registry_path).await?;
+
+    let normalized = normalize(path)?;
+    let owner = registry.owner_of(&normalized)
+        .ok_or_else(|| HpkgError::NotFound(format!("No installed package owns {}.", normalized.display())))?;
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "path": normalized,
+            "name": owner.name,
+            "version": owner.version,
+        }));
+        return Ok(());
+    }
+
+    println!("{} is owned by {} v{}", normalized.display(), owner.name, owner.version);
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::sbom::{self, SbomFormat};
+use hoshipkg::status;
+use hoshipkg::timestamp;
+use hoshipkg::ui;
+
+/// Prints an SBOM of every installed package, or writes it to `file`.
+pub async fn handle(format: SbomFormat, file: Option<&Path>) -> Result<()> {
+    let registry = PackageRegistry::load(&PackageRegistry::get_install_path()).await?;
+    let document = sbom::generate(format, &registry, timestamp::now());
+
+    let Some(file) = file else {
+        // The document is JSON either way, so it is the command's output in both modes.
+        ui::emit_json(&document);
+        return Ok(());
+    };
+
+    let mut content = serde_json::to_string_pretty(&document)?;
+    content.push('\n');
+    tokio::fs::write(file, content).await
+        .map_err(|e| HpkgError::Io(format!("Failed to write {}: {}", file.display(), e)))?;
+
+    let count = registry.list_packages().len();
+    status!("Wrote a {} SBOM of {} packages to {}.", format.name(), count, file.display());
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "format": format.name(),
+            "path": file,
+            "packages": count,
+        }));
+    }
+    Ok(())
+}
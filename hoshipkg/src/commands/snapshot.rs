@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use hoshipkg::activation;
+use hoshipkg::config::HoshiConfig;
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::snapshot::SnapshotStore;
+use hoshipkg::status;
+use hoshipkg::timestamp;
+use hoshipkg::ui::{self, Options};
+
+pub async fn list() -> Result<()> {
+    let config = HoshiConfig::load().await?;
+    let snapshots = SnapshotStore::from_config(&config).list().await?;
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({ "snapshots": snapshots }));
+        return Ok(());
+    }
+
+    if snapshots.is_empty() {
+        println!("No snapshots.");
+        return Ok(());
+    }
+    for snapshot in snapshots.iter().rev() {
+        println!(
+            "{:>4}  {}  {} ({} directories)",
+            snapshot.id,
+            timestamp::format_utc(snapshot.timestamp),
+            snapshot.command,
+            snapshot.paths.len()
+        );
+    }
+    Ok(())
+}
+
+pub async fn restore(id: u64, options: &Options) -> Result<()> {
+    let config = HoshiConfig::load().await?;
+    let store = SnapshotStore::from_config(&config);
+    let snapshot = store.get(id).await?;
+
+    status!("Restoring snapshot {} taken before '{}' at {}:", snapshot.id, snapshot.command, timestamp::format_utc(snapshot.timestamp));
+    status!(" - registry");
+    for recorded in &snapshot.paths {
+        let action = if recorded.existed { "restore" } else { "remove" };
+        status!(" - {} {}", action, recorded.path.display());
+    }
+
+    if options.dry_run {
+        status!("\nDry run, nothing was changed.");
+        if ui::is_json() {
+            ui::emit_json(&serde_json::json!({ "dry_run": true, "snapshot": snapshot }));
+        }
+        return Ok(());
+    }
+
+    if !ui::confirm(&format!("Do you want to restore snapshot {}?", snapshot.id), options) {
+        return Err(HpkgError::UserAbort("Restore aborted by user.".to_string()));
+    }
+
+    let registry_path = PackageRegistry::get_install_path();
+    store.restore(&snapshot, &registry_path).await?;
+
+    // The restored directories may not match the active links any more.
+    let mut registry = PackageRegistry::load(&registry_path).await?;
+    activation::relink(&mut registry).await?;
+    let mut roots: Vec<&Path> = snapshot.paths.iter()
+        .filter_map(|p| p.path.parent().and_then(Path::parent))
+        .chain(registry.list_packages().into_iter().filter_map(activation::install_root_of))
+        .collect();
+    roots.sort();
+    roots.dedup();
+    for root in roots {
+        activation::prune_dangling(root).await?;
+    }
+    if snapshot.had_registry {
+        registry.save(&registry_path).await?;
+    }
+    status!("Snapshot {} restored.", snapshot.id);
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({ "dry_run": false, "snapshot": snapshot }));
+    }
+    Ok(())
+}
@@ -1,36 +1,53 @@
-use webfetch;
-use crate::commands::merge::{Constellation, ConstellationMetadata};
+use hoshipkg::advisory::{self, AdvisoryDb, SyncedAdvisories};
+use hoshipkg::constellation::{self, Constellation};
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::status;
+use hoshipkg::timestamp;
+use hoshipkg::ui;
 
-pub async fn handle(constellation_name: &str) {
-    println!("Attempting to sync constellation: {}", constellation_name);
+pub async fn handle(constellation_name: &str) -> Result<()> {
+    status!("Attempting to sync constellation: {}", constellation_name);
 
-    let constellations = Constellation::default_constellations();
-    let target_constellation = constellations.iter()
-        .find(|c| c.name.eq_ignore_ascii_case(constellation_name));
+    let constellations = Constellation::load_all().await?;
+    let constellation = constellations.iter()
+        .find(|c| c.name.eq_ignore_ascii_case(constellation_name))
+        .ok_or_else(|| HpkgError::NotFound(format!("Constellation '{}' not found.", constellation_name)))?;
 
-    if let Some(constellation) = target_constellation {
-        println!("Syncing: {}", constellation.name);
+    status!("Syncing: {}", constellation.name);
 
-        match webfetch::fetch_url_to_string(&constellation.metadata_url).await {
-            Ok(metadata_content) => {
-                match serde_json::from_str::<ConstellationMetadata>(&metadata_content) {
-                    Ok(meta) => {
-                        println!("\nSuccessfully synced constellation: {}", meta.name);
-                        println!("Found {} packages.", meta.packages.len());
-                    },
-                    Err(e) => {
-                        eprintln!("Error parsing metadata for {}: {}", constellation.name, e);
-                        panic!("Failed to parse metadata for {}: {}", constellation.name, e);
-                    }
-                }
+    let (meta, served_by) = constellation::fetch_metadata(constellation).await?;
+    status!("\nSuccessfully synced constellation: {} (from {})", meta.name, served_by);
+    status!("Found {} packages.", meta.packages.len());
+
+    let advisory_path = AdvisoryDb::get_default_path();
+    let mut advisories = AdvisoryDb::load(&advisory_path).await?;
+    let mut advisory_count = None;
+    match &meta.advisories_url {
+        Some(url) => match advisory::fetch(url).await {
+            Ok(list) => {
+                status!("Found {} advisories.", list.advisories.len());
+                advisory_count = Some(list.advisories.len());
+                advisories.update(&constellation.name, Some(SyncedAdvisories {
+                    fetched_at: timestamp::now(),
+                    source: url.clone(),
+                    advisories: list.advisories,
+                }));
             },
-            Err(e) => {
-                eprintln!("Error fetching metadata for {}: {}", constellation.name, e);
-                panic!("Failed to fetch metadata for {}: {}", constellation.name, e);
-            }
-        }
-        println!("Sync complete.");
-    } else {
-        panic!("Constellation '{}' not found.", constellation_name);
+            // The package metadata is still good, so keep the advisories from the last sync.
+            Err(e) => eprintln!("Warning: could not fetch advisories for {}, keeping the previous ones: {}", constellation.name, e),
+        },
+        None => advisories.update(&constellation.name, None),
+    }
+    advisories.save(&advisory_path).await?;
+    status!("Sync complete.");
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "constellation": constellation.name,
+            "served_by": served_by,
+            "packages": meta.packages,
+            "advisories": advisory_count,
+        }));
     }
+    Ok(())
 }
@@ -0,0 +1,206 @@
+use std::path::PathBuf;
+use serde::Serialize;
+
+use hoshipkg::cache::{CacheEntry, PackageCache};
+use hoshipkg::checksum;
+use hoshipkg::config::HoshiConfig;
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::history::{ChangeAction, History, HistoryEntry, PackageChange};
+use hoshipkg::registry::{InstallReason, PackageRegistry, PackageSource};
+use hoshipkg::snapshot;
+use hoshipkg::status;
+use hoshipkg::transaction::{self, Transaction};
+use hoshipkg::ui::{self, Options};
+use hoshipkg::version;
+
+/// One step of reverting a transaction.
+#[derive(Debug, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum UndoStep {
+    Remove {
+        name: String,
+        version: String,
+    },
+    Restore {
+        name: String,
+        version: String,
+        archive: PathBuf,
+        reason: InstallReason,
+        dependencies: Vec<String>,
+        source: Option<PackageSource>,
+        license: Option<String>,
+    },
+}
+
+/// Finds how `name` v`version` was last installed according to the history, so restoring it
+/// keeps its reason and dependencies.
+fn recorded_install<'a>(entries: &'a [HistoryEntry], before: u64, name: &str, version: &str) -> Option<&'a PackageChange> {
+    entries.iter()
+        .filter(|e| e.id < before)
+        .flat_map(|e| &e.changes)
+        .rfind(|c| c.name == name && version::compare_versions(&c.version, version).is_eq())
+}
+
+/// The cached archive of `name` v`version`, verified before it is used. With a recorded source
+/// only the archive with that checksum will do; without one, any archive cached under its
+/// published checksum.
+async fn find_archive(cached: &[CacheEntry], name: &str, version: &str, source: Option<&PackageSource>) -> Result<PathBuf> {
+    let candidates = cached.iter()
+        .filter(|c| c.name == name && version::compare_versions(&c.version, version).is_eq());
+    for entry in candidates {
+        let bucket = entry.dir().file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let expected = match source {
+            Some(source) => source.checksum.as_str(),
+            None if bucket != "unverified" => bucket,
+            None => continue,
+        };
+        if checksum::sha256_file(&entry.path).await?.eq_ignore_ascii_case(expected) {
+            return Ok(entry.path.clone());
+        }
+    }
+    Err(HpkgError::NotFound(format!(
+        "No cached archive of {} v{} matches the one that was installed; it cannot be restored. \
+         Fetch it with 'hpkg download' first.",
+        name, version
+    )))
+}
+
+pub async fn handle(id: u64, options: &Options) -> Result<()> {
+    let history = History::open(&History::get_default_path());
+    let entries = history.entries().await?;
+    let entry = entries.iter().find(|e| e.id == id)
+        .ok_or_else(|| HpkgError::NotFound(format!("No transaction with id {} in the history.", id)))?;
+
+    let registry_path = PackageRegistry::get_install_path();
+    let mut registry = PackageRegistry::load(&registry_path).await?;
+    let config = HoshiConfig::load().await?;
+    let cache = PackageCache::from_config(&config);
+    let cached = cache.entries().await?;
+
+    let is_installed = |registry: &PackageRegistry, name: &str, version: &str| {
+        registry.versions_of(name).iter().any(|p| version::compare_versions(&p.version, version).is_eq())
+    };
+
+    let mut steps = Vec::new();
+    for change in entry.changes.iter().rev() {
+        match change.action {
+            ChangeAction::Reinstall => {},
+            ChangeAction::Install | ChangeAction::Upgrade | ChangeAction::Downgrade => {
+                if is_installed(&registry, &change.name, &change.version) {
+                    steps.push(UndoStep::Remove { name: change.name.clone(), version: change.version.clone() });
+                }
+                if let Some(previous) = &change.previous_version {
+                    if !is_installed(&registry, &change.name, previous) {
+                        let recorded = recorded_install(&entries, entry.id, &change.name, previous);
+                        let source = recorded.and_then(|r| r.source.clone());
+                        steps.push(UndoStep::Restore {
+                            name: change.name.clone(),
+                            version: previous.clone(),
+                            archive: find_archive(&cached, &change.name, previous, source.as_ref()).await?,
+                            reason: recorded.map_or(change.reason, |r| r.reason),
+                            dependencies: recorded.map_or_else(Vec::new, |r| r.dependencies.clone()),
+                            source,
+                            license: recorded.and_then(|r| r.license.clone()),
+                        });
+                    }
+                }
+            },
+            ChangeAction::Remove => {
+                if !is_installed(&registry, &change.name, &change.version) {
+                    steps.push(UndoStep::Restore {
+                        name: change.name.clone(),
+                        version: change.version.clone(),
+                        archive: find_archive(&cached, &change.name, &change.version, change.source.as_ref()).await?,
+                        reason: change.reason,
+                        dependencies: change.dependencies.clone(),
+                        source: change.source.clone(),
+                        license: change.license.clone(),
+                    });
+                }
+            },
+        }
+    }
+
+    status!("Undoing transaction {} ({}):", entry.id, entry.command);
+    if steps.is_empty() {
+        status!("Nothing to do, the installation already matches the state before it.");
+    }
+    for step in &steps {
+        match step {
+            UndoStep::Remove { name, version } => status!(" - remove {} v{}", name, version),
+            UndoStep::Restore { name, version, archive, .. } => {
+                status!(" - restore {} v{} from {}", name, version, archive.display())
+            },
+        }
+    }
+
+    if options.dry_run || steps.is_empty() {
+        if options.dry_run {
+            status!("\nDry run, nothing was changed.");
+        }
+        if ui::is_json() {
+            ui::emit_json(&serde_json::json!({ "undone": entry.id, "dry_run": options.dry_run, "steps": steps, "history_id": null }));
+        }
+        return Ok(());
+    }
+
+    if !ui::confirm(&format!("Do you want to undo transaction {}?", entry.id), options) {
+        return Err(HpkgError::UserAbort("Undo aborted by user.".to_string()));
+    }
+
+    let install_root = transaction::prepare_install_root(&Transaction::default_install_root()).await?;
+    let affected: Vec<PathBuf> = steps.iter()
+        .map(|step| match step {
+            UndoStep::Remove { name, version } | UndoStep::Restore { name, version, .. } => {
+                install_root.join(name).join(version)
+            },
+        })
+        .collect();
+    if let Some(snapshot) = snapshot::take(&config, &registry_path, &affected).await? {
+        status!("Snapshot {} taken.", snapshot.id);
+    }
+
+    let mut changes = Vec::new();
+    for step in &steps {
+        match step {
+            UndoStep::Remove { name, version } => {
+                if let Some((removed, _)) = transaction::uninstall(&mut registry, name, version).await? {
+                    status!("Removed {} v{}", name, version);
+                    changes.push(PackageChange::removed(&removed));
+                }
+            },
+            UndoStep::Restore { name, version, archive, reason, dependencies, source, license } => {
+                let mut installed = transaction::install_archive(
+                    archive, &install_root, name, version, *reason, dependencies.clone(), None,
+                ).await?;
+                installed.source = source.clone();
+                installed.license = license.clone();
+                status!("Restored {} v{}", name, version);
+                changes.push(PackageChange {
+                    name: installed.name.clone(),
+                    action: ChangeAction::Install,
+                    version: installed.version.clone(),
+                    previous_version: None,
+                    reason: installed.reason,
+                    dependencies: installed.dependencies.clone(),
+                    source: installed.source.clone(),
+                    license: installed.license.clone(),
+                });
+                registry.add(installed);
+                transaction::activate(&mut registry, name, version).await;
+            },
+        }
+    }
+
+    registry.save(&registry_path).await?;
+    status!("Package registry updated.");
+
+    let history_id = history.append(HistoryEntry::new(changes)).await?;
+    if let Some(history_id) = history_id {
+        status!("Recorded as transaction {}.", history_id);
+    }
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({ "undone": entry.id, "dry_run": false, "steps": steps, "history_id": history_id }));
+    }
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use hoshipkg::activation;
+use hoshipkg::error::Result;
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::status;
+use hoshipkg::ui::{self, Options};
+
+pub async fn handle(package_name: &str, package_version: &str, options: &Options) -> Result<()> {
+    let registry_path = PackageRegistry::get_install_path();
+    let mut registry = PackageRegistry::load(&registry_path).await?;
+    let previous = registry.active_version(package_name).map(str::to_string);
+
+    if options.dry_run {
+        let target = activation::find_installed(&registry, package_name, package_version)?.version.clone();
+        status!("Would make {} v{} active.", package_name, target);
+        status!("Dry run, nothing was changed.");
+        if ui::is_json() {
+            ui::emit_json(&serde_json::json!({
+                "dry_run": true,
+                "name": package_name,
+                "version": target,
+                "previous_version": previous,
+            }));
+        }
+        return Ok(());
+    }
+
+    let report = activation::activate(&mut registry, package_name, package_version).await?;
+    registry.save(&registry_path).await?;
+    let active = registry.active_version(package_name).unwrap_or(package_version).to_string();
+
+    match &previous {
+        Some(previous) if previous != &active => status!("{} v{} is now active (was v{}).", package_name, active, previous),
+        _ => status!("{} v{} is now active.", package_name, active),
+    }
+    for link in &report.unlinked {
+        status!(" - {}", link.display());
+    }
+    for link in &report.linked {
+        status!(" + {}", link.display());
+    }
+    for conflict in &report.conflicts {
+        status!("Not linking {}: another package already provides it.", conflict.display());
+    }
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "dry_run": false,
+            "name": package_name,
+            "version": active,
+            "previous_version": previous,
+            "linked": report.linked,
+            "unlinked": report.unlinked,
+            "conflicts": report.conflicts,
+        }));
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use serde::Serialize;
+
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::manifest::{self, VerifyReport};
+use hoshipkg::registry::{InstalledPackage, PackageRegistry};
+use hoshipkg::status;
+use hoshipkg::ui;
+use hoshipkg::version;
+
+#[derive(Debug, Serialize)]
+struct PackageVerification<'a> {
+    name: &'a str,
+    version: &'a str,
+    /// False when the package was installed before file lists were recorded, so nothing could be checked.
+    checked: bool,
+    #[serde(flatten)]
+    report: VerifyReport,
+}
+
+pub async fn handle(package_names: &[String]) -> Result<()> {
+    let registry_path = PackageRegistry::get_install_path();
+    let registry = PackageRegistry::load(&registry_path).await?;
+
+    let mut packages: Vec<&InstalledPackage> = if package_names.is_empty() {
+        registry.list_packages()
+    } else {
+        let mut selected = Vec::new();
+        for name in package_names {
+            let versions = registry.versions_of(name);
+            if versions.is_empty() {
+                return Err(HpkgError::NotFound(format!("Package '{}' is not installed.", name)));
+            }
+            selected.extend(versions);
+        }
+        selected
+    };
+    packages.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| version::compare_versions(&a.version, &b.version)));
+
+    let mut results = Vec::new();
+    for pkg in packages {
+        let checked = !pkg.files.is_empty();
+        let report = if checked {
+            manifest::verify(&pkg.install_path, &pkg.files).await
+                .map_err(|e| HpkgError::Io(format!("Failed to verify {} v{}: {}", pkg.name, pkg.version, e)))?
+        } else {
+            VerifyReport::default()
+        };
+        results.push(PackageVerification { name: &pkg.name, version: &pkg.version, checked, report });
+    }
+
+    for result in &results {
+        if !result.checked {
+            status!("{} v{}: no file list recorded, skipped", result.name, result.version);
+            continue;
+        }
+        if result.report.is_clean() {
+            status!("{} v{}: ok", result.name, result.version);
+            continue;
+        }
+
+        status!("{} v{}:", result.name, result.version);
+        for file in &result.report.modified {
+            status!("  modified: {} ({})", file.path.display(), file.changes.join(", "));
+        }
+        for path in &result.report.missing {
+            status!("  missing:  {}", path.display());
+        }
+        for path in &result.report.extra {
+            status!("  extra:    {}", path.display());
+        }
+    }
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({ "packages": results }));
+    }
+
+    let failing = results.iter().filter(|r| !r.report.is_clean()).count();
+    if failing > 0 {
+        return Err(HpkgError::Integrity(format!(
+            "{} of {} packages differ from what was installed.",
+            failing,
+            results.len()
+        )));
+    }
+    Ok(())
+}
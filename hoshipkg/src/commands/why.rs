@@ -0,0 +1,92 @@
+use serde::Serialize;
+
+use hoshipkg::error::{HpkgError, Result};
+use hoshipkg::registry::{InstallReason, InstalledPackage, PackageRegistry};
+use hoshipkg::status;
+use hoshipkg::ui;
+
+/// A path of reverse dependencies, starting with a package that requires the queried one and
+/// ending with the package that explains it.
+#[derive(Debug, Serialize)]
+struct Chain<'a> {
+    packages: Vec<&'a InstalledPackage>,
+    /// False when the chain ends at a dependency that nothing requires any more.
+    ends_at_explicit: bool,
+}
+
+fn collect_chains<'a>(
+    registry: &'a PackageRegistry,
+    name: &str,
+    path: &mut Vec<&'a InstalledPackage>,
+    visited: &mut Vec<String>,
+    chains: &mut Vec<Chain<'a>>,
+) {
+    let dependents = registry.dependents_of(name);
+    if dependents.is_empty() && !path.is_empty() {
+        chains.push(Chain { packages: path.clone(), ends_at_explicit: false });
+        return;
+    }
+
+    for dependent in dependents {
+        // A dependency cycle would otherwise recurse forever.
+        if visited.contains(&dependent.name) {
+            continue;
+        }
+        path.push(dependent);
+        if dependent.reason == InstallReason::Explicit {
+            chains.push(Chain { packages: path.clone(), ends_at_explicit: true });
+        } else {
+            visited.push(dependent.name.clone());
+            collect_chains(registry, &dependent.name, path, visited, chains);
+            visited.pop();
+        }
+        path.pop();
+    }
+}
+
+pub async fn handle(package_name: &str) -> Result<()> {
+    let registry_path = PackageRegistry::get_install_path();
+    let registry = PackageRegistry::load(&registry_path).await?;
+
+    let installed = registry.versions_of(package_name);
+    if installed.is_empty() {
+        return Err(HpkgError::NotFound(format!("Package '{}' is not installed.", package_name)));
+    }
+    let explicit = registry.is_explicit(package_name);
+
+    let mut chains = Vec::new();
+    let mut visited = vec![package_name.to_string()];
+    collect_chains(&registry, package_name, &mut Vec::new(), &mut visited, &mut chains);
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "name": package_name,
+            "installed": installed,
+            "explicit": explicit,
+            "chains": chains,
+        }));
+        return Ok(());
+    }
+
+    let versions: Vec<String> = installed.iter().map(|p| format!("v{}", p.version)).collect();
+    status!("{} {}", package_name, versions.join(", "));
+    if explicit {
+        status!("  was installed explicitly");
+    }
+    if chains.is_empty() {
+        if !explicit {
+            status!("  is not required by any installed package");
+        }
+        return Ok(());
+    }
+
+    status!("  is required by:");
+    for chain in &chains {
+        let links: Vec<String> = chain.packages.iter()
+            .map(|p| format!("{} v{}", p.name, p.version))
+            .collect();
+        let end = if chain.ends_at_explicit { "explicit" } else { "orphaned, nothing requires it" };
+        status!("    {} <- {} ({})", package_name, links.join(" <- "), end);
+    }
+    Ok(())
+}
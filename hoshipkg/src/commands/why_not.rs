@@ -0,0 +1,156 @@
+use hoshipkg::advisory::AdvisoryDb;
+use hoshipkg::config::HoshiConfig;
+use hoshipkg::constellation::{self, Constellation, PackageMetadata};
+use hoshipkg::error::Result;
+use hoshipkg::registry::PackageRegistry;
+use hoshipkg::resolver;
+use hoshipkg::status;
+use hoshipkg::ui;
+use hoshipkg::version;
+
+fn priority_of(constellations: &[Constellation], name: &str) -> i32 {
+    constellations.iter().find(|c| c.name == name).map_or(0, |c| c.priority)
+}
+
+fn describe_candidates(candidates: &[&PackageMetadata]) -> String {
+    candidates.iter()
+        .map(|p| format!("v{} from {}", p.version, p.constellation))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Explains why `hpkg merge <spec>` would not install `wanted_version`, or would fail outright.
+pub async fn handle(package_spec: &str, wanted_version: Option<&str>) -> Result<()> {
+    let constellations = Constellation::load_all().await?;
+    let available = constellation::sync_constellations(&constellations).await?;
+    let registry_path = PackageRegistry::get_install_path();
+    let registry = PackageRegistry::load(&registry_path).await?;
+    let config = HoshiConfig::load().await?;
+    let advisories = AdvisoryDb::load(&AdvisoryDb::get_default_path()).await?;
+
+    let (name, pin) = resolver::parse_package_spec(package_spec);
+    let mut candidates: Vec<&PackageMetadata> = available.iter().filter(|p| p.name == name).collect();
+    candidates.sort_by(|a, b| version::compare_versions(&b.version, &a.version));
+    let selected = resolver::select_package(&available, &constellations, name, pin);
+
+    let mut reasons: Vec<String> = Vec::new();
+
+    if candidates.is_empty() {
+        let configured: Vec<&str> = constellations.iter().map(|c| c.name.as_str()).collect();
+        reasons.push(format!("'{}' is not provided by any configured constellation ({}).", name, configured.join(", ")));
+    } else if let (Some(pin), None) = (pin, selected) {
+        if !constellations.iter().any(|c| c.name.eq_ignore_ascii_case(pin)) {
+            reasons.push(format!("No constellation named '{}' is configured.", pin));
+        } else {
+            reasons.push(format!("'{}' is not in constellation '{}'.", name, pin));
+        }
+        reasons.push(format!("It is available as {}.", describe_candidates(&candidates)));
+    }
+
+    if let (Some(wanted), Some(selected)) = (wanted_version, selected) {
+        let wanted_candidates: Vec<&PackageMetadata> = candidates.iter()
+            .copied()
+            .filter(|p| version::compare_versions(&p.version, wanted).is_eq())
+            .collect();
+
+        if wanted_candidates.is_empty() {
+            reasons.push(format!("Version {} of '{}' is not published; available: {}.", wanted, name, describe_candidates(&candidates)));
+        } else if !version::compare_versions(&selected.version, wanted).is_eq() {
+            let selected_priority = priority_of(&constellations, &selected.constellation);
+            for candidate in &wanted_candidates {
+                let candidate_priority = priority_of(&constellations, &candidate.constellation);
+                if pin.is_some_and(|pin| !candidate.constellation.eq_ignore_ascii_case(pin)) {
+                    reasons.push(format!(
+                        "v{} is only in '{}', but the spec pins '{}'.",
+                        candidate.version, candidate.constellation, pin.unwrap_or_default()
+                    ));
+                } else if candidate_priority < selected_priority {
+                    reasons.push(format!(
+                        "v{} from {} is picked over v{} because {} has priority {} and {} has priority {}.",
+                        selected.version, selected.constellation, candidate.version,
+                        selected.constellation, selected_priority, candidate.constellation, candidate_priority
+                    ));
+                } else {
+                    reasons.push(format!(
+                        "v{} from {} is newer than v{} from {} at the same priority.",
+                        selected.version, selected.constellation, candidate.version, candidate.constellation
+                    ));
+                }
+
+                let pinned = resolver::select_package(&available, &constellations, name, Some(&candidate.constellation));
+                if pinned.is_some_and(|p| version::compare_versions(&p.version, wanted).is_eq()) && pin.is_none() {
+                    reasons.push(format!("'{}@{}' would select v{}.", name, candidate.constellation, candidate.version));
+                }
+            }
+        }
+    }
+
+    if let Some(selected) = selected {
+        match resolver::resolve(&available, &constellations, &[package_spec]) {
+            Err(e) => reasons.push(format!("Merging '{}' would fail: {}", package_spec, e)),
+            Ok(resolved) => {
+                for pkg in &resolved {
+                    for dep_spec in pkg.dependencies.iter().flatten() {
+                        let (dep_name, dep_pin) = resolver::parse_package_spec(dep_spec);
+                        if resolver::select_package(&available, &constellations, dep_name, dep_pin).is_none() {
+                            reasons.push(format!(
+                                "Dependency '{}' of {} v{} is not available; the merge would continue without it.",
+                                dep_spec, pkg.name, pkg.version
+                            ));
+                        }
+                    }
+                }
+                for violation in config.license_policy.violations(&resolved, &[name.to_string()]) {
+                    reasons.push(format!(
+                        "The license policy rejects {} ({}): {}.",
+                        violation.chain.join(" -> "),
+                        violation.license.as_deref().unwrap_or("no license"),
+                        violation.reason
+                    ));
+                }
+                let policy = &config.advisory_policy;
+                for pkg in &resolved {
+                    let refused = policy.affecting(&advisories, Some(&pkg.constellation), &pkg.name, &pkg.version)
+                        .into_iter()
+                        .filter(|m| policy.refuses(m.advisory.severity));
+                    for m in refused {
+                        reasons.push(format!(
+                            "{} v{} is affected by {} ({}), which the advisory policy refuses: {}",
+                            pkg.name, pkg.version, m.advisory.id, m.advisory.severity, m.advisory.description
+                        ));
+                    }
+                }
+            }
+        }
+
+        if registry.versions_of(name).iter().any(|p| version::compare_versions(&p.version, &selected.version).is_eq()) {
+            reasons.push(format!("v{} is already installed; merging would reinstall it.", selected.version));
+        }
+    }
+
+    if ui::is_json() {
+        ui::emit_json(&serde_json::json!({
+            "spec": package_spec,
+            "version": wanted_version,
+            "selected": selected.map(|p| serde_json::json!({
+                "name": p.name,
+                "version": p.version,
+                "constellation": p.constellation,
+            })),
+            "reasons": reasons,
+        }));
+        return Ok(());
+    }
+
+    match selected {
+        Some(p) => status!("\n'{}' resolves to {} v{} from {}.", package_spec, p.name, p.version, p.constellation),
+        None => status!("\n'{}' cannot be resolved.", package_spec),
+    }
+    if reasons.is_empty() {
+        status!("Nothing would prevent the merge.");
+    }
+    for reason in reasons {
+        status!(" - {}", reason);
+    }
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::error::{HpkgError, Result};
+use crate::policy::{AdvisoryPolicy, LicensePolicy};
+
+fn default_max_parallel_downloads() -> usize {
+    4
+}
+
+fn default_max_snapshots() -> usize {
+    10
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HoshiConfig {
+    #[serde(default = "default_max_parallel_downloads")]
+    pub max_parallel_downloads: usize,
+    /// Where downloaded archives are kept. Defaults to the user cache directory.
+    #[serde(default)]
+    pub cache_dir: Option<PathBuf>,
+    /// How many pre-transaction snapshots to keep; older ones are pruned. 0 disables snapshots.
+    #[serde(default = "default_max_snapshots")]
+    pub max_snapshots: usize,
+    /// Where snapshots are stored. Defaults to the user data directory.
+    #[serde(default)]
+    pub snapshot_dir: Option<PathBuf>,
+    /// Licenses `merge` and `apply` accept. Empty by default, which accepts everything.
+    #[serde(default)]
+    pub license_policy: LicensePolicy,
+    /// What `merge` and `apply` do about versions with known advisories. Warns by default.
+    #[serde(default)]
+    pub advisory_policy: AdvisoryPolicy,
+}
+
+impl Default for HoshiConfig {
+    fn default() -> Self {
+        HoshiConfig {
+            max_parallel_downloads: default_max_parallel_downloads(),
+            cache_dir: None,
+            max_snapshots: default_max_snapshots(),
+            snapshot_dir: None,
+            license_policy: LicensePolicy::default(),
+            advisory_policy: AdvisoryPolicy::default(),
+        }
+    }
+}
+
+impl HoshiConfig {
+    pub fn get_config_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("hoshi")
+            .join("config.json")
+    }
+
+    pub async fn load() -> Result<Self> {
+        let path = Self::get_config_path();
+        if !path.exists() {
+            return Ok(HoshiConfig::default());
+        }
+
+        let content = tokio::fs::read_to_string(&path).await
+            .map_err(|e| HpkgError::Io(format!("Failed to read config {}: {}", path.display(), e)))?;
+        serde_json::from_str(&content)
+            .map_err(|e| HpkgError::Parse(format!("Invalid config {}: {}", path.display(), e)))
+    }
+}
@@ -0,0 +1,50 @@
+//! Syncing constellations. The metadata schema, config types and fetching live in `hoshi-core`.
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+pub use hoshi_core::{Constellation, ConstellationMetadata, PackageMetadata};
+
+use crate::error::Result;
+use crate::status;
+
+/// Fetches the metadata from the first mirror that responds with valid JSON.
+/// Returns the metadata and the location that served it.
+pub async fn fetch_metadata(constellation: &Constellation) -> Result<(ConstellationMetadata, String)> {
+    Ok(hoshi_core::fetch::fetch_metadata(constellation).await?)
+}
+
+/// Fetches every constellation, returning all packages they provide.
+/// Individual failures are reported as warnings; it only fails if no constellation could be synced.
+pub async fn sync_constellations(constellations: &[Constellation]) -> Result<Vec<PackageMetadata>> {
+    status!("\nStarting constellation sync...");
+
+    let mut all_available_packages: Vec<PackageMetadata> = Vec::new();
+    let mut last_error = None;
+
+    let pb = ProgressBar::new(constellations.len() as u64);
+    let style = ProgressStyle::with_template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
+        .unwrap();
+    pb.set_style(style);
+
+    for (i, constellation) in constellations.iter().enumerate() {
+        pb.set_message(format!("Syncing: {}", constellation.name));
+        pb.set_position(i as u64);
+
+        match fetch_metadata(constellation).await {
+            Ok((mut meta, served_by)) => {
+                status!("Successfully synced constellation: {} (from {})", meta.name, served_by);
+                all_available_packages.append(&mut meta.packages);
+            },
+            Err(e) => {
+                eprintln!("Error syncing metadata for {}: {}", constellation.name, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    pb.finish_with_message("Constellation sync complete.");
+
+    match last_error {
+        Some(e) if all_available_packages.is_empty() => Err(e),
+        _ => Ok(all_available_packages),
+    }
+}
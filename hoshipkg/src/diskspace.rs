@@ -0,0 +1,86 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::HpkgError;
+
+/// Walks up to the closest existing ancestor, since the install root or cache may not exist yet.
+fn existing_ancestor(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    absolute.ancestors()
+        .find(|p| p.exists())
+        .map(Path::to_path_buf)
+        .unwrap_or(absolute)
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`.
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let existing = existing_ancestor(path);
+    let c_path = CString::new(existing.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn available_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "free space check is only supported on unix"))
+}
+
+/// Whether two paths live on the same filesystem, so their space requirements add up.
+#[cfg(unix)]
+pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (std::fs::metadata(existing_ancestor(a)), std::fs::metadata(existing_ancestor(b))) {
+        (Ok(a_meta), Ok(b_meta)) => a_meta.dev() == b_meta.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn same_filesystem(_a: &Path, _b: &Path) -> bool {
+    false
+}
+
+/// Checks that every filesystem has room for the bytes required on it.
+/// Requirements on the same filesystem are summed before comparing.
+pub fn ensure_space(requirements: &[(&Path, u64)]) -> Result<(), HpkgError> {
+    let mut filesystems: Vec<(Vec<&Path>, u64)> = Vec::new();
+    for (path, bytes) in requirements {
+        match filesystems.iter_mut().find(|(paths, _)| same_filesystem(paths[0], path)) {
+            Some((paths, total)) => {
+                paths.push(path);
+                *total += bytes;
+            },
+            None => filesystems.push((vec![path], *bytes)),
+        }
+    }
+
+    for (paths, required) in filesystems {
+        let available = match available_space(paths[0]) {
+            Ok(available) => available,
+            Err(e) => {
+                eprintln!("Warning: Could not check free space for {}: {}", paths[0].display(), e);
+                continue;
+            }
+        };
+        if required > available {
+            let locations: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+            return Err(HpkgError::InsufficientSpace(format!(
+                "Not enough disk space on the filesystem holding {}: {} required, {} available.",
+                locations.join(" and "),
+                crate::cache::format_size(required),
+                crate::cache::format_size(available),
+            )));
+        }
+    }
+    Ok(())
+}
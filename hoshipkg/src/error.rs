@@ -0,0 +1,105 @@
+use std::fmt;
+
+/// Everything that can make an hpkg command fail. Each variant maps to a stable
+/// machine-readable code and a distinct process exit code so scripts can branch on it.
+#[derive(Debug)]
+pub enum HpkgError {
+    /// A remote could not be reached, answered with an HTTP error, or served a bad file.
+    Network(String),
+    /// Metadata, config or registry content could not be parsed.
+    Parse(String),
+    /// A package, constellation or file that was asked for does not exist.
+    NotFound(String),
+    /// The request contradicts itself or the current installation.
+    Conflict(String),
+    /// Reading or writing local files failed.
+    Io(String),
+    /// There is not enough free disk space for the transaction.
+    InsufficientSpace(String),
+    /// The user declined the confirmation prompt, or there was no terminal to ask on.
+    UserAbort(String),
+    /// Installed files no longer match what was recorded at install time.
+    Integrity(String),
+    /// Installed or requested packages are affected by known security advisories.
+    Advisory(String),
+}
+
+pub type Result<T> = std::result::Result<T, HpkgError>;
+
+impl HpkgError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            HpkgError::Network(_) => "network",
+            HpkgError::Parse(_) => "parse",
+            HpkgError::NotFound(_) => "not_found",
+            HpkgError::Conflict(_) => "conflict",
+            HpkgError::Io(_) => "io",
+            HpkgError::InsufficientSpace(_) => "insufficient_space",
+            HpkgError::UserAbort(_) => "aborted",
+            HpkgError::Integrity(_) => "integrity",
+            HpkgError::Advisory(_) => "advisory",
+        }
+    }
+
+    /// Codes 1 and 2 are left for generic failures and usage errors, so these start at 3.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            HpkgError::Network(_) => 3,
+            HpkgError::Parse(_) => 4,
+            HpkgError::NotFound(_) => 5,
+            HpkgError::Conflict(_) => 6,
+            HpkgError::Io(_) => 7,
+            HpkgError::InsufficientSpace(_) => 8,
+            HpkgError::UserAbort(_) => 9,
+            HpkgError::Integrity(_) => 10,
+            HpkgError::Advisory(_) => 11,
+        }
+    }
+}
+
+impl fmt::Display for HpkgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HpkgError::Network(msg) => write!(f, "Network error: {}", msg),
+            HpkgError::Parse(msg) => write!(f, "Parse error: {}", msg),
+            HpkgError::NotFound(msg) => write!(f, "{}", msg),
+            HpkgError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            HpkgError::Io(msg) => write!(f, "I/O error: {}", msg),
+            HpkgError::InsufficientSpace(msg) => write!(f, "{}", msg),
+            HpkgError::UserAbort(msg) => write!(f, "{}", msg),
+            HpkgError::Integrity(msg) => write!(f, "{}", msg),
+            HpkgError::Advisory(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for HpkgError {}
+
+impl From<std::io::Error> for HpkgError {
+    fn from(e: std::io::Error) -> Self {
+        HpkgError::Io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for HpkgError {
+    fn from(e: serde_json::Error) -> Self {
+        HpkgError::Parse(e.to_string())
+    }
+}
+
+impl From<hoshi_core::CoreError> for HpkgError {
+    fn from(e: hoshi_core::CoreError) -> Self {
+        match e {
+            hoshi_core::CoreError::Io { .. } => HpkgError::Io(e.to_string()),
+            hoshi_core::CoreError::Network { .. } => HpkgError::Network(e.to_string()),
+            hoshi_core::CoreError::NoMetadataLocations { .. } => HpkgError::NotFound(e.to_string()),
+            _ => HpkgError::Parse(e.to_string()),
+        }
+    }
+}
+
+impl From<tokio::task::JoinError> for HpkgError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        HpkgError::Io(format!("background task failed: {}", e))
+    }
+}
@@ -0,0 +1,196 @@
+//! An append-only log of every transaction, one JSON object per line.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use crate::error::{HpkgError, Result};
+use crate::plan::PlanAction;
+use crate::registry::{InstallReason, InstalledPackage, PackageSource};
+use crate::timestamp;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Install,
+    Reinstall,
+    Upgrade,
+    Downgrade,
+    Remove,
+}
+
+impl From<PlanAction> for ChangeAction {
+    fn from(action: PlanAction) -> Self {
+        match action {
+            PlanAction::Install => ChangeAction::Install,
+            PlanAction::Reinstall => ChangeAction::Reinstall,
+            PlanAction::Upgrade => ChangeAction::Upgrade,
+            PlanAction::Downgrade => ChangeAction::Downgrade,
+        }
+    }
+}
+
+/// One package a transaction touched.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PackageChange {
+    pub name: String,
+    pub action: ChangeAction,
+    /// The version installed, or removed for `Remove`.
+    pub version: String,
+    /// The newest version that was installed before an upgrade or downgrade.
+    #[serde(default)]
+    pub previous_version: Option<String>,
+    /// Kept so undoing a removal restores the package as it was.
+    pub reason: InstallReason,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Where the archive came from, so undo restores exactly that archive. `None` for entries
+    /// written before sources were recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PackageSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+}
+
+impl PackageChange {
+    /// The record of uninstalling `removed`, with enough of it kept for undo to restore it.
+    pub fn removed(removed: &InstalledPackage) -> Self {
+        PackageChange {
+            name: removed.name.clone(),
+            action: ChangeAction::Remove,
+            version: removed.version.clone(),
+            previous_version: None,
+            reason: removed.reason,
+            dependencies: removed.dependencies.clone(),
+            source: removed.source.clone(),
+            license: removed.license.clone(),
+        }
+    }
+}
+
+impl fmt::Display for PackageChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.action, &self.previous_version) {
+            (ChangeAction::Install, _) => write!(f, "+{} v{}", self.name, self.version),
+            (ChangeAction::Remove, _) => write!(f, "-{} v{}", self.name, self.version),
+            (ChangeAction::Reinstall, _) => write!(f, "{} v{} (reinstall)", self.name, self.version),
+            (_, Some(previous)) => write!(f, "{} v{} -> v{}", self.name, previous, self.version),
+            (_, None) => write!(f, "{} v{}", self.name, self.version),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// Unix seconds.
+    pub timestamp: u64,
+    pub user: String,
+    /// The command line that ran the transaction.
+    pub command: String,
+    pub changes: Vec<PackageChange>,
+}
+
+impl HistoryEntry {
+    /// Uses the current time, user and command line; `History::append` assigns the id.
+    pub fn new(changes: Vec<PackageChange>) -> Self {
+        HistoryEntry {
+            id: 0,
+            timestamp: timestamp::now(),
+            user: current_user(),
+            command: command_line(),
+            changes,
+        }
+    }
+}
+
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn get_default_path() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("hoshi")
+            .join("history.jsonl")
+    }
+
+    pub fn open(path: &Path) -> Self {
+        History { path: path.to_path_buf() }
+    }
+
+    /// Every recorded transaction, oldest first.
+    pub async fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = tokio::fs::read_to_string(&self.path).await
+            .map_err(|e| HpkgError::Io(format!("Failed to read history {}: {}", self.path.display(), e)))?;
+        content.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| serde_json::from_str(line).map_err(|e| {
+                HpkgError::Parse(format!("History {} line {} is corrupted: {}", self.path.display(), i + 1, e))
+            }))
+            .collect()
+    }
+
+    pub async fn get(&self, id: u64) -> Result<HistoryEntry> {
+        self.entries().await?
+            .into_iter()
+            .find(|e| e.id == id)
+            .ok_or_else(|| HpkgError::NotFound(format!("No transaction with id {} in the history.", id)))
+    }
+
+    /// Appends `entry` with the next free id and returns that id. Transactions without changes are not recorded.
+    pub async fn append(&self, mut entry: HistoryEntry) -> Result<Option<u64>> {
+        if entry.changes.is_empty() {
+            return Ok(None);
+        }
+        entry.id = self.entries().await?.last().map_or(1, |last| last.id + 1);
+
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await
+                .map_err(|e| HpkgError::Io(format!("Failed to create history directory {}: {}", parent.display(), e)))?;
+        }
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| HpkgError::Io(format!("Failed to open history {}: {}", self.path.display(), e)))?;
+        file.write_all(line.as_bytes()).await
+            .map_err(|e| HpkgError::Io(format!("Failed to write history {}: {}", self.path.display(), e)))?;
+        Ok(Some(entry.id))
+    }
+}
+
+/// The running command as the user typed it, with the binary shown as `hpkg`.
+pub fn command_line() -> String {
+    std::iter::once("hpkg".to_string())
+        .chain(std::env::args().skip(1))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| fallback_user())
+}
+
+#[cfg(unix)]
+fn fallback_user() -> String {
+    format!("uid {}", unsafe { libc::getuid() })
+}
+
+#[cfg(not(unix))]
+fn fallback_user() -> String {
+    "unknown".to_string()
+}
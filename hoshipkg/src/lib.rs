@@ -1,2 +1,70 @@
-// This file is empty, hm?
-// lib.rs isn't really too needed.
+//! The Hoshi package manager as a library. The `hpkg` binary is a thin CLI over this crate.
+//! Metadata and config types come from `hoshi-core` and are re-exported here.
+//!
+//! A typical install goes through these modules in order:
+//!
+//! 1. [`constellation`]: load the configured [`Constellation`]s and sync their [`PackageMetadata`].
+//! 2. [`resolver`]: pick candidates for the requested specs and their dependencies.
+//! 3. [`transaction`]: plan the [`Transaction`] against the [`PackageRegistry`] and [`PackageCache`],
+//!    then download, extract and register the packages.
+//!
+//! ```no_run
+//! use hoshipkg::cache::PackageCache;
+//! use hoshipkg::config::HoshiConfig;
+//! use hoshipkg::constellation::{self, Constellation};
+//! use hoshipkg::registry::PackageRegistry;
+//! use hoshipkg::scheduler::DownloadScheduler;
+//! use hoshipkg::transaction::Transaction;
+//! use hoshipkg::resolver;
+//!
+//! # async fn run() -> hoshipkg::error::Result<()> {
+//! let constellations = Constellation::load_all().await?;
+//! let available = constellation::sync_constellations(&constellations).await?;
+//! let packages = resolver::resolve(&available, &constellations, &["hello"])?;
+//!
+//! let config = HoshiConfig::load().await?;
+//! let registry_path = PackageRegistry::get_install_path();
+//! let mut registry = PackageRegistry::load(&registry_path).await?;
+//!
+//! let cache = PackageCache::from_config(&config);
+//! let explicit = vec!["hello".to_string()];
+//! let transaction = Transaction::new(packages, explicit, &registry, cache, Transaction::default_install_root());
+//! transaction.check_space(false)?;
+//! let downloaded = transaction.download(&DownloadScheduler::new(config.max_parallel_downloads)).await?;
+//! transaction.install(&downloaded, &mut registry).await?;
+//! registry.save(&registry_path).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod activation;
+pub mod advisory;
+pub mod cache;
+pub mod checksum;
+pub mod config;
+pub mod constellation;
+pub mod diskspace;
+pub mod error;
+pub mod history;
+pub mod lockfile;
+pub mod manifest;
+pub mod plan;
+pub mod policy;
+pub mod registry;
+pub mod resolver;
+pub mod sbom;
+pub mod scheduler;
+pub mod snapshot;
+pub mod source;
+pub mod timestamp;
+pub mod transaction;
+pub mod ui;
+pub mod world;
+
+pub use hoshi_core::{license, version};
+
+pub use cache::PackageCache;
+pub use constellation::{Constellation, ConstellationMetadata, PackageMetadata};
+pub use error::{HpkgError, Result};
+pub use registry::{InstallReason, InstalledPackage, PackageRegistry};
+pub use transaction::Transaction;
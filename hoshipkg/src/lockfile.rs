@@ -0,0 +1,216 @@
+//! Lockfiles: the exact set of installed packages, written by `hpkg lock` and reproduced by
+//! `hpkg install --locked`.
+
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::PackageCache;
+use crate::checksum;
+use crate::constellation::{Constellation, PackageMetadata};
+use crate::error::{HpkgError, Result};
+use crate::registry::{InstallReason, InstalledPackage, PackageRegistry, PackageSource};
+use crate::resolver;
+use crate::version;
+
+/// The newest lockfile format this build reads and the one it writes.
+pub const LOCK_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    pub constellation: String,
+    pub download_url: String,
+    /// Lowercase hex SHA-256 of the archive.
+    pub checksum: String,
+    pub reason: InstallReason,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+}
+
+impl LockedPackage {
+    fn new(package: &InstalledPackage, source: PackageSource) -> Self {
+        LockedPackage {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            constellation: source.constellation,
+            download_url: source.download_url,
+            checksum: source.checksum,
+            reason: package.reason,
+            dependencies: package.dependencies.clone(),
+            license: package.license.clone(),
+        }
+    }
+
+    /// Metadata for downloading exactly this archive from exactly this location.
+    pub fn to_metadata(&self) -> PackageMetadata {
+        PackageMetadata {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            description: String::new(),
+            download_url: self.download_url.clone(),
+            size_mb: 0,
+            installed_size_mb: None,
+            archive_type: String::new(),
+            dependencies: Some(self.dependencies.clone()),
+            checksum: Some(self.checksum.clone()),
+            license: self.license.clone(),
+            executables: None,
+            mirror_urls: Vec::new(),
+            constellation: self.constellation.clone(),
+        }
+    }
+
+    /// Whether `installed` came from the same archive.
+    pub fn matches(&self, installed: &InstalledPackage) -> bool {
+        version::compare_versions(&installed.version, &self.version).is_eq()
+            && installed.source.as_ref().is_some_and(|s| s.checksum.eq_ignore_ascii_case(&self.checksum))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub lock_version: u32,
+    pub packages: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    pub fn get_default_path() -> PathBuf {
+        PathBuf::from("hoshi.lock")
+    }
+
+    /// Locks every package in `registry`. Entries installed before sources were recorded are looked
+    /// up in `available`, and hashed from the cache when their metadata has no checksum.
+    pub async fn from_registry(
+        registry: &PackageRegistry,
+        available: &[PackageMetadata],
+        constellations: &[Constellation],
+        cache: &PackageCache,
+    ) -> Result<Self> {
+        let mut installed = registry.list_packages();
+        installed.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| version::compare_versions(&a.version, &b.version)));
+
+        let mut packages = Vec::new();
+        let mut unlockable = Vec::new();
+        for package in installed {
+            match source_of(package, available, constellations, cache).await? {
+                Some(source) => packages.push(LockedPackage::new(package, source)),
+                None => unlockable.push(format!("{} v{}", package.name, package.version)),
+            }
+        }
+
+        if !unlockable.is_empty() {
+            return Err(HpkgError::NotFound(format!(
+                "Cannot tell which archive {} came from; no constellation publishes them with a checksum and \
+                 they are not in the cache. Reinstall them with 'hpkg merge' to lock them.",
+                unlockable.join(", ")
+            )));
+        }
+        Ok(Lockfile { lock_version: LOCK_VERSION, packages })
+    }
+
+    pub async fn load(path: &Path) -> Result<Self> {
+        let content = tokio::fs::read_to_string(path).await
+            .map_err(|e| HpkgError::Io(format!("Failed to read lockfile {}: {}", path.display(), e)))?;
+        let lockfile: Lockfile = serde_json::from_str(&content)
+            .map_err(|e| HpkgError::Parse(format!("Invalid lockfile {}: {}", path.display(), e)))?;
+        if lockfile.lock_version > LOCK_VERSION {
+            return Err(HpkgError::Parse(format!(
+                "Lockfile {} has version {}, newer than the supported version {}.",
+                path.display(), lockfile.lock_version, LOCK_VERSION
+            )));
+        }
+        Ok(lockfile)
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        let mut content = serde_json::to_string_pretty(self)?;
+        content.push('\n');
+        tokio::fs::write(path, content).await
+            .map_err(|e| HpkgError::Io(format!("Failed to write lockfile {}: {}", path.display(), e)))
+    }
+
+    pub fn get(&self, name: &str, version: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| p.name == name && version::compare_versions(&p.version, version).is_eq())
+    }
+}
+
+async fn source_of(
+    package: &InstalledPackage,
+    available: &[PackageMetadata],
+    constellations: &[Constellation],
+    cache: &PackageCache,
+) -> Result<Option<PackageSource>> {
+    if let Some(source) = &package.source {
+        return Ok(Some(source.clone()));
+    }
+
+    let candidates: Vec<PackageMetadata> = available.iter()
+        .filter(|p| p.name == package.name && version::compare_versions(&p.version, &package.version).is_eq())
+        .cloned()
+        .collect();
+    // Several constellations may publish the same version; prefer the one a merge would pick.
+    let Some(metadata) = resolver::select_package(&candidates, constellations, &package.name, None) else {
+        return Ok(None);
+    };
+
+    let checksum = match &metadata.checksum {
+        Some(checksum) => checksum.to_ascii_lowercase(),
+        None => {
+            let archive = cache.archive_path(metadata);
+            if !archive.exists() {
+                return Ok(None);
+            }
+            checksum::sha256_file(&archive).await?
+        },
+    };
+    Ok(Some(PackageSource {
+        constellation: metadata.constellation.clone(),
+        download_url: metadata.download_url.clone(),
+        checksum,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECKSUM: &str = "ed7fda264b14942061232722841f73d091a30968e181b117cea80cecc3ad0d51";
+
+    fn installed(version: &str, source: Option<PackageSource>) -> InstalledPackage {
+        InstalledPackage {
+            name: "libstar".to_string(),
+            version: version.to_string(),
+            install_path: PathBuf::from("/hoshi_packages/libstar").join(version),
+            installed_at: None,
+            reason: InstallReason::Explicit,
+            dependencies: Vec::new(),
+            files: Vec::new(),
+            executables: None,
+            source,
+            license: None,
+        }
+    }
+
+    fn source(checksum: &str) -> PackageSource {
+        PackageSource {
+            constellation: "Hoshi Core".to_string(),
+            download_url: "https://example.org/libstar-1.0.tar.gz".to_string(),
+            checksum: checksum.to_string(),
+        }
+    }
+
+    #[test]
+    fn matches_the_same_archive_only() {
+        let locked = LockedPackage::new(&installed("1.0", None), source(CHECKSUM));
+
+        assert!(locked.matches(&installed("1.0", Some(source(CHECKSUM)))));
+        assert!(locked.matches(&installed("1", Some(source(&CHECKSUM.to_uppercase())))));
+        assert!(!locked.matches(&installed("1.1", Some(source(CHECKSUM)))));
+        assert!(!locked.matches(&installed("1.0", Some(source(&CHECKSUM.replace('e', "f"))))));
+        // Entries installed before sources were recorded can't be shown to match.
+        assert!(!locked.matches(&installed("1.0", None)));
+    }
+}
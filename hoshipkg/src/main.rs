@@ -1,13 +1,24 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 mod commands;
-mod registry;
 use crate::commands::list;
+use hoshipkg::sbom::SbomFormat;
+use hoshipkg::ui::{self, Options, OutputFormat};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
+    #[arg(short, long, global = true, visible_alias = "noconfirm")]
+    yes: bool,
+
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -16,32 +27,228 @@ struct Cli {
 enum Commands {
     Merge {
         name: String,
+
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Write the archives to --dest as a file:// constellation instead of installing them
+        #[arg(long, requires = "dest")]
+        download_only: bool,
+
+        #[arg(short, long, requires = "download_only")]
+        dest: Option<PathBuf>,
+    },
+    /// Install, upgrade and remove packages so the installation matches a world file
+    Apply {
+        /// Defaults to world.json in the config directory
+        file: Option<PathBuf>,
+
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    /// Install exactly the packages recorded in a lockfile
+    Install {
+        #[arg(long, value_name = "FILE")]
+        locked: PathBuf,
+
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    /// Write a lockfile recording every installed package's exact version and archive
+    Lock {
+        /// Defaults to hoshi.lock in the current directory
+        file: Option<PathBuf>,
+    },
+    /// Report installed packages affected by known security advisories
+    Audit,
+    /// Export a software bill of materials of the installed packages
+    Sbom {
+        #[arg(long, value_enum, default_value_t = SbomFormat::SpdxJson)]
+        format: SbomFormat,
+
+        /// Defaults to standard output
+        file: Option<PathBuf>,
     },
     Sync {
         constellation: String,
     },
     List,
+    /// Show metadata, install status and install details for a package
+    Info {
+        name: String,
+    },
+    /// List the files an installed package owns
+    Files {
+        name: String,
+    },
+    /// Find the installed package that owns a file
+    Owns {
+        path: PathBuf,
+    },
+    /// Check installed files against what was recorded at install time
+    Verify {
+        packages: Vec<String>,
+    },
+    /// Show the chains of installed packages that require a package
+    Why {
+        name: String,
+    },
+    /// Explain why a merge would fail or pick a different version
+    WhyNot {
+        name: String,
+
+        #[arg(value_name = "VERSION")]
+        wanted_version: Option<String>,
+    },
     Delete {
         name: String,
+
+        /// Required when several versions are installed
+        #[arg(value_name = "VERSION")]
+        package_version: Option<String>,
+    },
+    /// Make an installed version of a package the active one
+    Use {
+        name: String,
+
+        #[arg(value_name = "VERSION")]
+        package_version: String,
+    },
+    /// Print shell code that puts the active packages' executables on the PATH
+    Env {
+        /// Defaults to the shell in $SHELL
+        #[arg(long, value_enum)]
+        shell: Option<commands::env::Shell>,
+    },
+    /// List past transactions, or show one in detail
+    History {
+        id: Option<u64>,
+    },
+    /// Revert a transaction, restoring removed versions from the package cache
+    Undo {
+        id: u64,
+    },
+    /// Manage the snapshots taken before each transaction
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+    Download {
+        #[arg(required = true)]
+        packages: Vec<String>,
+
+        #[arg(short, long)]
+        dest: PathBuf,
+
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    Clean {
+        #[arg(long, conflicts_with_all = ["uninstalled", "older_than"])]
+        all: bool,
+
+        #[arg(long)]
+        uninstalled: bool,
+
+        /// Remove cached archives downloaded more than this many days ago
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommands {
+    List,
+    Restore {
+        id: u64,
     },
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
+    let options = Options {
+        assume_yes: cli.yes,
+        dry_run: cli.dry_run,
+        output: cli.output,
+    };
+    ui::init(&options);
 
-    match &cli.command {
-        Commands::Merge { name } => {
-            commands::merge::handle(name).await;
+    let result = match &cli.command {
+        Commands::Merge { name, jobs, download_only, dest } => {
+            let download_dest = if *download_only { dest.as_deref() } else { None };
+            commands::merge::handle(name, *jobs, download_dest, &options).await
+        },
+        Commands::Apply { file, jobs } => {
+            commands::apply::handle(file.as_deref(), *jobs, &options).await
+        },
+        Commands::Install { locked, jobs } => {
+            commands::install::handle_locked(locked, *jobs, &options).await
+        },
+        Commands::Lock { file } => {
+            commands::lock::handle(file.as_deref(), &options).await
+        },
+        Commands::Audit => {
+            commands::audit::handle().await
+        },
+        Commands::Sbom { format, file } => {
+            commands::sbom::handle(*format, file.as_deref()).await
         },
         Commands::Sync { constellation } => {
-            commands::sync::handle(constellation).await;
+            commands::sync::handle(constellation).await
         },
         Commands::List => {
-            list::handle().await;
+            list::handle().await
         },
-        Commands::Delete { name } => {
-            commands::delete::handle(name).await;
+        Commands::Info { name } => {
+            commands::info::handle(name).await
         },
+        Commands::Files { name } => {
+            commands::files::handle(name).await
+        },
+        Commands::Owns { path } => {
+            commands::owns::handle(path).await
+        },
+        Commands::Verify { packages } => {
+            commands::verify::handle(packages).await
+        },
+        Commands::Why { name } => {
+            commands::why::handle(name).await
+        },
+        Commands::WhyNot { name, wanted_version } => {
+            commands::why_not::handle(name, wanted_version.as_deref()).await
+        },
+        Commands::Delete { name, package_version } => {
+            commands::delete::handle(name, package_version.as_deref(), &options).await
+        },
+        Commands::Use { name, package_version } => {
+            commands::use_version::handle(name, package_version, &options).await
+        },
+        Commands::Env { shell } => {
+            commands::env::handle(*shell).await
+        },
+        Commands::History { id } => {
+            commands::history::handle(*id).await
+        },
+        Commands::Undo { id } => {
+            commands::undo::handle(*id, &options).await
+        },
+        Commands::Snapshot { command: SnapshotCommands::List } => {
+            commands::snapshot::list().await
+        },
+        Commands::Snapshot { command: SnapshotCommands::Restore { id } } => {
+            commands::snapshot::restore(*id, &options).await
+        },
+        Commands::Download { packages, dest, jobs } => {
+            commands::download::handle(packages, dest, *jobs, &options).await
+        },
+        Commands::Clean { all, uninstalled, older_than } => {
+            commands::clean::handle(*all, *uninstalled, *older_than, &options).await
+        },
+    };
+
+    if let Err(e) = result {
+        ui::report_error(&e);
+        std::process::exit(e.exit_code());
     }
 }
@@ -0,0 +1,176 @@
+//! The list of files a package installed, recorded in the registry at install time.
+
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::checksum;
+
+/// One installed file. The attributes are `None` for entries recorded before they were kept,
+/// and those are then not checked by `verify`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct InstalledFile {
+    /// Relative to the package's install path.
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Permission bits, e.g. `0o755`. Only recorded on Unix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    /// Lowercase hex SHA-256 of the contents. Not recorded for symlinks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Where the file pointed if it was a symlink.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModifiedFile {
+    pub path: PathBuf,
+    /// Which attributes changed: `size`, `mode`, `sha256`, `link_target` or `type`.
+    pub changes: Vec<&'static str>,
+}
+
+/// The differences between an install directory and its recorded file list.
+#[derive(Debug, Default, Serialize)]
+pub struct VerifyReport {
+    pub modified: Vec<ModifiedFile>,
+    pub missing: Vec<PathBuf>,
+    /// Files present in the install directory that the package did not install.
+    pub extra: Vec<PathBuf>,
+}
+
+impl VerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.modified.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+/// Lists every file and symlink under `dir` with its size, permissions and digest, sorted by path.
+/// Directories themselves are not listed.
+pub async fn scan(dir: &Path) -> io::Result<Vec<InstalledFile>> {
+    let dir = dir.to_path_buf();
+    tokio::task::spawn_blocking(move || scan_blocking(&dir))
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// The blocking version of `scan`.
+pub fn scan_blocking(dir: &Path) -> io::Result<Vec<InstalledFile>> {
+    let mut paths = Vec::new();
+    walk(dir, dir, &mut paths)?;
+    paths.sort();
+    paths.into_iter().map(|path| describe(dir, path)).collect()
+}
+
+/// Compares the files under `dir` against `recorded`, hashing every file that still exists.
+pub async fn verify(dir: &Path, recorded: &[InstalledFile]) -> io::Result<VerifyReport> {
+    let dir = dir.to_path_buf();
+    let recorded = recorded.to_vec();
+    tokio::task::spawn_blocking(move || {
+        let mut report = VerifyReport::default();
+
+        for expected in &recorded {
+            let full_path = dir.join(&expected.path);
+            let metadata = match std::fs::symlink_metadata(&full_path) {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    report.missing.push(expected.path.clone());
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            let actual = describe_with(&full_path, expected.path.clone(), &metadata)?;
+            let changes = compare(expected, &actual);
+            if !changes.is_empty() {
+                report.modified.push(ModifiedFile { path: expected.path.clone(), changes });
+            }
+        }
+
+        if dir.exists() {
+            let known: HashSet<&Path> = recorded.iter().map(|f| f.path.as_path()).collect();
+            let mut present = Vec::new();
+            walk(&dir, &dir, &mut present)?;
+            present.sort();
+            report.extra = present.into_iter().filter(|p| !known.contains(p.as_path())).collect();
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+fn compare(expected: &InstalledFile, actual: &InstalledFile) -> Vec<&'static str> {
+    if expected.link_target.is_some() != actual.link_target.is_some() {
+        return vec!["type"];
+    }
+
+    let mut changes = Vec::new();
+    if expected.link_target.is_some() && expected.link_target != actual.link_target {
+        changes.push("link_target");
+    }
+    if expected.size.is_some() && expected.size != actual.size {
+        changes.push("size");
+    }
+    if expected.mode.is_some() && expected.mode != actual.mode {
+        changes.push("mode");
+    }
+    if expected.sha256.is_some() && expected.sha256 != actual.sha256 {
+        changes.push("sha256");
+    }
+    changes
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            walk(root, &path, files)?;
+        } else {
+            files.push(path.strip_prefix(root).map(Path::to_path_buf).unwrap_or(path));
+        }
+    }
+    Ok(())
+}
+
+fn describe(root: &Path, relative: PathBuf) -> io::Result<InstalledFile> {
+    let full_path = root.join(&relative);
+    let metadata = std::fs::symlink_metadata(&full_path)?;
+    describe_with(&full_path, relative, &metadata)
+}
+
+fn describe_with(full_path: &Path, relative: PathBuf, metadata: &Metadata) -> io::Result<InstalledFile> {
+    if metadata.file_type().is_symlink() {
+        return Ok(InstalledFile {
+            path: relative,
+            size: None,
+            mode: None,
+            sha256: None,
+            link_target: Some(std::fs::read_link(full_path)?),
+        });
+    }
+
+    Ok(InstalledFile {
+        path: relative,
+        size: Some(metadata.len()),
+        mode: permission_bits(metadata),
+        sha256: Some(checksum::sha256_file_blocking(full_path)?),
+        link_target: None,
+    })
+}
+
+#[cfg(unix)]
+fn permission_bits(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn permission_bits(_metadata: &Metadata) -> Option<u32> {
+    None
+}
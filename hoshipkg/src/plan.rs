@@ -0,0 +1,120 @@
+use serde::Serialize;
+
+use crate::cache::{self, PackageCache};
+use crate::constellation::PackageMetadata;
+use crate::registry::PackageRegistry;
+use crate::status;
+use crate::version;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanAction {
+    Install,
+    Reinstall,
+    Upgrade,
+    Downgrade,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlanEntry {
+    pub name: String,
+    pub version: String,
+    pub constellation: String,
+    pub action: PlanAction,
+    /// The newest version already installed, if any.
+    pub installed_version: Option<String>,
+    pub download_url: String,
+    pub cached: bool,
+    pub download_bytes: u64,
+    pub installed_bytes: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct PlanRemoval {
+    pub name: String,
+    pub version: String,
+}
+
+/// Everything a transaction is about to do, computed before touching anything.
+#[derive(Debug, Serialize)]
+pub struct InstallPlan {
+    pub installs: Vec<PlanEntry>,
+    pub removals: Vec<PlanRemoval>,
+    pub download_bytes: u64,
+    pub installed_bytes: u64,
+}
+
+impl InstallPlan {
+    pub fn new(packages: &[PackageMetadata], registry: &PackageRegistry, cache: &PackageCache) -> Self {
+        let installed = registry.list_packages();
+        let mut installs = Vec::new();
+
+        for pkg in packages {
+            let installed_version = installed.iter()
+                .filter(|p| p.name == pkg.name)
+                .map(|p| p.version.as_str())
+                .max_by(|a, b| version::compare_versions(a, b));
+
+            let action = match installed_version {
+                None => PlanAction::Install,
+                Some(current) => match version::compare_versions(&pkg.version, current) {
+                    std::cmp::Ordering::Greater => PlanAction::Upgrade,
+                    std::cmp::Ordering::Less => PlanAction::Downgrade,
+                    std::cmp::Ordering::Equal => PlanAction::Reinstall,
+                },
+            };
+
+            let cached = cache.contains(pkg);
+            installs.push(PlanEntry {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                constellation: pkg.constellation.clone(),
+                action,
+                installed_version: installed_version.map(str::to_string),
+                download_url: pkg.download_url.clone(),
+                cached,
+                download_bytes: if cached { 0 } else { pkg.size_mb as u64 * 1024 * 1024 },
+                installed_bytes: pkg.installed_size_mb.unwrap_or(pkg.size_mb) as u64 * 1024 * 1024,
+            });
+        }
+
+        let download_bytes = installs.iter().map(|e| e.download_bytes).sum();
+        let installed_bytes = installs.iter().map(|e| e.installed_bytes).sum();
+        InstallPlan {
+            installs,
+            removals: Vec::new(),
+            download_bytes,
+            installed_bytes,
+        }
+    }
+
+    pub fn print(&self, header: &str, show_installed_size: bool) {
+        status!("\n{}", header);
+        for entry in &self.installs {
+            let action = match (entry.action, &entry.installed_version) {
+                (PlanAction::Upgrade, Some(from)) => format!("upgrade from v{}", from),
+                (PlanAction::Downgrade, Some(from)) => format!("downgrade from v{}", from),
+                (PlanAction::Reinstall, _) => "reinstall".to_string(),
+                _ => "install".to_string(),
+            };
+            let download = if entry.cached {
+                "cached".to_string()
+            } else {
+                format!("{} from {}", cache::format_size(entry.download_bytes), entry.download_url)
+            };
+            status!(" - {} v{} from {} [{}] ({})", entry.name, entry.version, entry.constellation, action, download);
+        }
+
+        if !self.removals.is_empty() {
+            status!("\nPackages to remove:");
+            for removal in &self.removals {
+                status!(" - {} v{}", removal.name, removal.version);
+            }
+        }
+
+        status!("\nTotal download size: {}", cache::format_size(self.download_bytes));
+        if show_installed_size {
+            status!("Total installed size: {}", cache::format_size(self.installed_bytes));
+        }
+    }
+}
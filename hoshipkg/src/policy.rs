@@ -0,0 +1,330 @@
+//! Install-time policies, checked by `merge` and `apply` before anything is downloaded.
+//! Both are configured in the config file:
+//!
+//! ```json
+//! {
+//!   "license_policy": { "allow": ["MIT", "Apache-2.0"], "deny": ["GPL-3.0-only"], "allow_unlicensed": false },
+//!   "advisory_policy": { "refuse_at": "high", "ignore": ["HOSHI-2026-0001"] }
+//! }
+//! ```
+//!
+//! A package passes the license policy when some choice its license expression offers uses only
+//! licenses that are not denied and, if `allow` is non-empty, are allowed. Identifiers compare
+//! case-insensitively.
+
+use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+
+use crate::advisory::{AdvisoryDb, AdvisoryMatch, Severity};
+use crate::constellation::PackageMetadata;
+use crate::error::{HpkgError, Result};
+use crate::license::LicenseExpr;
+use crate::resolver;
+
+fn default_allow_unlicensed() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicensePolicy {
+    /// When non-empty, the only licenses a package may be used under.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Licenses a package may never be used under. Takes precedence over `allow`.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Whether packages that don't publish a license can be installed.
+    #[serde(default = "default_allow_unlicensed")]
+    pub allow_unlicensed: bool,
+}
+
+impl Default for LicensePolicy {
+    fn default() -> Self {
+        LicensePolicy {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            allow_unlicensed: default_allow_unlicensed(),
+        }
+    }
+}
+
+/// A package the policy rejects and how the install reaches it.
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseViolation {
+    pub name: String,
+    pub version: String,
+    pub license: Option<String>,
+    /// `name vversion` of each package from the requested one down to this one.
+    pub chain: Vec<String>,
+    pub reason: String,
+}
+
+impl LicensePolicy {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty() && self.allow_unlicensed
+    }
+
+    fn denies(&self, id: &str) -> bool {
+        self.deny.iter().any(|d| d.eq_ignore_ascii_case(id))
+    }
+
+    fn allows(&self, id: &str) -> bool {
+        !self.denies(id) && (self.allow.is_empty() || self.allow.iter().any(|a| a.eq_ignore_ascii_case(id)))
+    }
+
+    /// Why `license` is rejected, or `None` when it passes.
+    pub fn rejection(&self, license: Option<&str>) -> Option<String> {
+        let Some(license) = license else {
+            return (!self.allow_unlicensed).then(|| "it does not publish a license".to_string());
+        };
+        let expr: LicenseExpr = match license.parse() {
+            Ok(expr) => expr,
+            Err(e) => return Some(e.to_string()),
+        };
+        if expr.is_satisfied_by(&|id| self.allows(id)) {
+            return None;
+        }
+
+        let mut rejected: Vec<String> = expr.licenses().into_iter()
+            .filter(|id| !self.allows(id))
+            .map(|id| if self.denies(id) { format!("{} is denied", id) } else { format!("{} is not allowed", id) })
+            .collect();
+        rejected.sort();
+        rejected.dedup();
+        Some(rejected.join(", "))
+    }
+
+    /// Fails with `Conflict` when any of `packages` is rejected, naming the dependency chain from
+    /// the `explicit` packages to each rejected one.
+    pub fn check(&self, packages: &[PackageMetadata], explicit: &[String]) -> Result<()> {
+        let violations = self.violations(packages, explicit);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        let lines: Vec<String> = violations.iter()
+            .map(|v| format!(
+                "  {} ({}): {}",
+                v.chain.join(" -> "),
+                v.license.as_deref().unwrap_or("no license"),
+                v.reason
+            ))
+            .collect();
+        Err(HpkgError::Conflict(format!("The license policy rejects:\n{}", lines.join("\n"))))
+    }
+
+    pub fn violations(&self, packages: &[PackageMetadata], explicit: &[String]) -> Vec<LicenseViolation> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        packages.iter()
+            .filter_map(|package| {
+                let reason = self.rejection(package.license.as_deref())?;
+                Some(LicenseViolation {
+                    name: package.name.clone(),
+                    version: package.version.clone(),
+                    license: package.license.clone(),
+                    chain: chain_to(packages, explicit, package),
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The shortest path of dependencies from an explicit package to `target`, as `name vversion`.
+fn chain_to(packages: &[PackageMetadata], explicit: &[String], target: &PackageMetadata) -> Vec<String> {
+    let label = |p: &PackageMetadata| format!("{} v{}", p.name, p.version);
+    let find = |name: &str| packages.iter().position(|p| p.name == name);
+
+    let mut previous: Vec<Option<usize>> = vec![None; packages.len()];
+    let mut visited = vec![false; packages.len()];
+    let mut queue: VecDeque<usize> = explicit.iter().filter_map(|name| find(name)).collect();
+    for &i in &queue {
+        visited[i] = true;
+    }
+
+    while let Some(i) = queue.pop_front() {
+        if packages[i].name == target.name {
+            let mut chain = vec![label(&packages[i])];
+            let mut current = i;
+            while let Some(parent) = previous[current] {
+                chain.push(label(&packages[parent]));
+                current = parent;
+            }
+            chain.reverse();
+            return chain;
+        }
+        for spec in packages[i].dependencies.iter().flatten() {
+            if let Some(j) = find(resolver::parse_package_spec(spec).0) {
+                if !visited[j] {
+                    visited[j] = true;
+                    previous[j] = Some(i);
+                    queue.push_back(j);
+                }
+            }
+        }
+    }
+    vec![label(target)]
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdvisoryPolicy {
+    /// Refuse to install versions affected by an advisory of at least this severity. Versions
+    /// with less severe advisories, or any advisory when unset, are installed with a warning.
+    #[serde(default)]
+    pub refuse_at: Option<Severity>,
+    /// Advisory ids to disregard, in `audit` as well as when installing.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+impl AdvisoryPolicy {
+    /// Advisories in `db` that affect `version` of `name`, minus ignored ones.
+    pub fn affecting<'a>(
+        &self,
+        db: &'a AdvisoryDb,
+        constellation: Option<&str>,
+        name: &str,
+        version: &str,
+    ) -> Vec<AdvisoryMatch<'a>> {
+        let mut matches = db.affecting(constellation, name, version);
+        matches.retain(|m| !self.ignore.iter().any(|id| id.eq_ignore_ascii_case(&m.advisory.id)));
+        matches
+    }
+
+    /// Whether an advisory of `severity` blocks the install rather than warning about it.
+    pub fn refuses(&self, severity: Severity) -> bool {
+        self.refuse_at.is_some_and(|threshold| severity >= threshold)
+    }
+
+    /// Warns about `packages` with advisories, and fails with `Advisory` when any reach `refuse_at`.
+    pub fn check(&self, db: &AdvisoryDb, packages: &[PackageMetadata]) -> Result<()> {
+        let mut refused = Vec::new();
+        for package in packages {
+            for m in self.affecting(db, Some(&package.constellation), &package.name, &package.version) {
+                let line = format!(
+                    "{} v{}: {} ({}) {}",
+                    package.name, package.version, m.advisory.id, m.advisory.severity, m.advisory.description
+                );
+                if self.refuses(m.advisory.severity) {
+                    refused.push(format!("  {}", line));
+                } else {
+                    eprintln!("Warning: {}", line);
+                }
+            }
+        }
+
+        match self.refuse_at {
+            Some(threshold) if !refused.is_empty() => Err(HpkgError::Advisory(format!(
+                "Refusing to install versions with {} or more severe advisories:\n{}",
+                threshold,
+                refused.join("\n")
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::advisory::SyncedAdvisories;
+
+    fn package(name: &str, version: &str, license: Option<&str>, dependencies: &[&str]) -> PackageMetadata {
+        PackageMetadata {
+            name: name.to_string(),
+            version: version.to_string(),
+            description: String::new(),
+            download_url: format!("https://example.org/{}-{}.tar.gz", name, version),
+            size_mb: 1,
+            installed_size_mb: None,
+            archive_type: "tar.gz".to_string(),
+            dependencies: Some(dependencies.iter().map(|d| d.to_string()).collect()),
+            checksum: None,
+            license: license.map(str::to_string),
+            executables: None,
+            mirror_urls: Vec::new(),
+            constellation: "Hoshi Core".to_string(),
+        }
+    }
+
+    fn license_policy(json: &str) -> LicensePolicy {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn allow_list_accepts_any_allowed_choice() {
+        let policy = license_policy(r#"{"allow": ["mit", "Apache-2.0"]}"#);
+        assert_eq!(policy.rejection(Some("MIT")), None);
+        assert_eq!(policy.rejection(Some("GPL-3.0-only OR Apache-2.0")), None);
+        assert_eq!(policy.rejection(Some("MIT AND Zlib")).as_deref(), Some("Zlib is not allowed"));
+        assert_eq!(policy.rejection(None), None);
+    }
+
+    #[test]
+    fn deny_takes_precedence_over_allow() {
+        let policy = license_policy(r#"{"allow": ["GPL-3.0-only", "MIT"], "deny": ["gpl-3.0-only"], "allow_unlicensed": false}"#);
+        assert_eq!(policy.rejection(Some("MIT OR GPL-3.0-only")), None);
+        assert_eq!(
+            policy.rejection(Some("GPL-3.0-only AND (Zlib OR GPL-3.0-only)")).as_deref(),
+            Some("GPL-3.0-only is denied, Zlib is not allowed")
+        );
+        assert_eq!(policy.rejection(None).as_deref(), Some("it does not publish a license"));
+    }
+
+    #[test]
+    fn violations_name_the_dependency_chain() {
+        let policy = license_policy(r#"{"deny": ["GPL-3.0-only"]}"#);
+        let packages = [
+            package("comet", "2.1", Some("MIT"), &["nebula"]),
+            package("nebula", "0.4", Some("MIT"), &["libstar@Hoshi Core"]),
+            package("libstar", "1.0", Some("GPL-3.0-only"), &[]),
+        ];
+
+        let violations = policy.violations(&packages, &["comet".to_string()]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].chain, ["comet v2.1", "nebula v0.4", "libstar v1.0"]);
+        assert!(matches!(policy.check(&packages, &["comet".to_string()]), Err(HpkgError::Conflict(_))));
+        assert!(policy.check(&packages[..2], &["comet".to_string()]).is_ok());
+    }
+
+    fn advisory_db(advisories: &str) -> AdvisoryDb {
+        let mut db = AdvisoryDb::default();
+        db.update("Hoshi Core", Some(SyncedAdvisories {
+            fetched_at: 0,
+            source: "https://example.org/advisories.json".to_string(),
+            advisories: serde_json::from_str(advisories).unwrap(),
+        }));
+        db
+    }
+
+    fn advisory_policy(json: &str) -> AdvisoryPolicy {
+        serde_json::from_str(json).unwrap()
+    }
+
+    const ADVISORIES: &str = r#"[
+        {"id": "HOSHI-2026-0001", "package": "libstar", "affected": ["<1.1"], "severity": "medium", "description": "d"}
+    ]"#;
+
+    #[test]
+    fn refuses_advisories_at_or_above_the_threshold() {
+        let db = advisory_db(ADVISORIES);
+        let packages = [package("libstar", "1.0", None, &[])];
+
+        assert!(matches!(advisory_policy(r#"{"refuse_at": "low"}"#).check(&db, &packages), Err(HpkgError::Advisory(_))));
+        assert!(matches!(advisory_policy(r#"{"refuse_at": "medium"}"#).check(&db, &packages), Err(HpkgError::Advisory(_))));
+        assert!(advisory_policy(r#"{"refuse_at": "high"}"#).check(&db, &packages).is_ok());
+        assert!(advisory_policy("{}").check(&db, &packages).is_ok());
+        assert!(advisory_policy(r#"{"refuse_at": "low"}"#).check(&db, &[package("libstar", "1.1", None, &[])]).is_ok());
+    }
+
+    #[test]
+    fn ignored_advisories_are_disregarded() {
+        let db = advisory_db(ADVISORIES);
+        let packages = [package("libstar", "1.0", None, &[])];
+        let policy = advisory_policy(r#"{"refuse_at": "low", "ignore": ["hoshi-2026-0001"]}"#);
+
+        assert!(policy.affecting(&db, Some("Hoshi Core"), "libstar", "1.0").is_empty());
+        assert!(policy.check(&db, &packages).is_ok());
+    }
+}
@@ -3,16 +3,82 @@ use tokio::fs;
 use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 
+use crate::error::{HpkgError, Result};
+use crate::manifest::InstalledFile;
+use crate::version;
+
+/// Why a package is installed. Entries written before this was recorded count as explicit.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallReason {
+    /// The user asked for it by name.
+    #[default]
+    Explicit,
+    /// It was pulled in to satisfy another package's dependencies.
+    Dependency,
+}
+
+/// Where an installed package's archive came from.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PackageSource {
+    pub constellation: String,
+    pub download_url: String,
+    /// Lowercase hex SHA-256 of the archive that was installed.
+    pub checksum: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InstalledPackage {
     pub name: String,
     pub version: String,
     pub install_path: PathBuf,
+    /// Unix seconds; `None` for entries written before install dates were recorded.
+    #[serde(default)]
+    pub installed_at: Option<u64>,
+    #[serde(default)]
+    pub reason: InstallReason,
+    /// Names of the packages this one depends on, without constellation pins.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// What the package installed. Empty for entries written before file lists were recorded.
+    #[serde(default)]
+    pub files: Vec<InstalledFile>,
+    /// Files linked into the shared `bin/` while this version is active, relative to `install_path`.
+    /// `None` for entries written before executables were recorded; they are then discovered from `files`.
+    #[serde(default)]
+    pub executables: Option<Vec<PathBuf>>,
+    /// `None` for entries written before sources were recorded, and for versions restored by `undo`.
+    #[serde(default)]
+    pub source: Option<PackageSource>,
+    /// The SPDX license expression the constellation published, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+}
+
+impl InstalledPackage {
+    /// The registry key, `<name>-<version>`.
+    pub fn key(&self) -> String {
+        format!("{}-{}", self.name, self.version)
+    }
+
+    /// Absolute locations of the installed files.
+    pub fn file_paths(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        let root = std::path::absolute(&self.install_path).unwrap_or_else(|_| self.install_path.clone());
+        self.files.iter().map(move |f| root.join(&f.path))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct PackageRegistry {
     packages: HashMap<String, InstalledPackage>,
+    /// Package name to the version `hpkg use` selected. Kept in sync with the `active` links by
+    /// the `activation` module.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    active: HashMap<String, String>,
+    /// Absolute file path to the key of the package that installed it. Maintained by `add` and
+    /// `remove`, and built on load for registries written before it was stored.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    file_index: HashMap<PathBuf, String>,
 }
 
 impl PackageRegistry {
@@ -23,47 +89,127 @@ impl PackageRegistry {
             .join("registry.json")
     }
 
-    pub async fn load(path: &Path) -> Self {
+    pub async fn load(path: &Path) -> Result<Self> {
         if !path.exists() {
             if let Some(parent) = path.parent() {
-                tokio::fs::create_dir_all(parent).await.expect("Failed to create parent directory for registry");
+                tokio::fs::create_dir_all(parent).await
+                    .map_err(|e| HpkgError::Io(format!("Failed to create registry directory {}: {}", parent.display(), e)))?;
             }
-            return PackageRegistry::default();
+            return Ok(PackageRegistry::default());
         }
 
-        let content = fs::read_to_string(path).await.expect("Failed to read registry file");
-        serde_json::from_str(&content).expect("Failed to parse registry content")
+        let content = fs::read_to_string(path).await
+            .map_err(|e| HpkgError::Io(format!("Failed to read registry {}: {}", path.display(), e)))?;
+        let mut registry: Self = serde_json::from_str(&content)
+            .map_err(|e| HpkgError::Parse(format!("Registry {} is corrupted: {}", path.display(), e)))?;
+
+        if registry.file_index.is_empty() && registry.packages.values().any(|p| !p.files.is_empty()) {
+            let index: Vec<(PathBuf, String)> = registry.packages.iter()
+                .flat_map(|(key, pkg)| pkg.file_paths().map(move |file| (file, key.clone())))
+                .collect();
+            registry.file_index.extend(index);
+        }
+        Ok(registry)
     }
 
-    pub async fn save(&self, path: &Path) {
+    pub async fn save(&self, path: &Path) -> Result<()> {
         if let Some(parent) = path.parent() {
-            tokio::fs::create_dir_all(parent).await.expect("Failed to create parent directory for registry save");
+            tokio::fs::create_dir_all(parent).await
+                .map_err(|e| HpkgError::Io(format!("Failed to create registry directory {}: {}", parent.display(), e)))?;
         }
 
-        let content = serde_json::to_string_pretty(&self).expect("Failed to serialize registry");
-        fs::write(path, content).await.expect("Failed to write registry file");
+        let content = serde_json::to_string_pretty(&self)?;
+        fs::write(path, content).await
+            .map_err(|e| HpkgError::Io(format!("Failed to write registry {}: {}", path.display(), e)))
     }
 
     pub fn add(&mut self, package: InstalledPackage) {
-        let key = format!("{}-{}", package.name, package.version);
+        let key = package.key();
+        if let Some(previous) = self.packages.remove(&key) {
+            self.unindex(&previous);
+        }
+        for file in package.file_paths() {
+            self.file_index.insert(file, key.clone());
+        }
         self.packages.insert(key, package);
     }
 
+    /// Removes `version` of `name`, or its newest installed version.
     pub fn remove(&mut self, name: &str, version: Option<&str>) -> Option<InstalledPackage> {
         let key_to_remove = match version {
             Some(v) => format!("{}-{}", name, v),
-            None => {
-                if let Some((key, _)) = self.packages.iter().find(|(_, pkg)| pkg.name == name) {
-                    key.clone()
-                } else {
-                    return None;
-                }
-            }
+            None => self.versions_of(name).last()?.key(),
         };
-        self.packages.remove(&key_to_remove)
+        let removed = self.packages.remove(&key_to_remove)?;
+        self.unindex(&removed);
+        Some(removed)
+    }
+
+    fn unindex(&mut self, package: &InstalledPackage) {
+        let key = package.key();
+        for file in package.file_paths() {
+            if self.file_index.get(&file) == Some(&key) {
+                self.file_index.remove(&file);
+            }
+        }
+    }
+
+    /// The package that installed `path`, which must be absolute and free of `.`/`..` components.
+    pub fn owner_of(&self, path: &Path) -> Option<&InstalledPackage> {
+        self.file_index.get(path).and_then(|key| self.packages.get(key))
     }
 
     pub fn list_packages(&self) -> Vec<&InstalledPackage> {
         self.packages.values().collect()
     }
+
+    /// Every installed version of `name`, oldest first.
+    pub fn versions_of(&self, name: &str) -> Vec<&InstalledPackage> {
+        let mut versions: Vec<&InstalledPackage> = self.packages.values().filter(|p| p.name == name).collect();
+        versions.sort_by(|a, b| version::compare_versions(&a.version, &b.version));
+        versions
+    }
+
+    /// Installed packages that depend on `name`, sorted by name and version.
+    pub fn dependents_of(&self, name: &str) -> Vec<&InstalledPackage> {
+        let mut dependents: Vec<&InstalledPackage> = self.packages.values()
+            .filter(|p| p.dependencies.iter().any(|d| d == name))
+            .collect();
+        dependents.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| version::compare_versions(&a.version, &b.version)));
+        dependents
+    }
+
+    /// The version of `name` that is active, if one was selected. It may no longer be installed.
+    pub fn active_version(&self, name: &str) -> Option<&str> {
+        self.active.get(name).map(String::as_str)
+    }
+
+    pub fn is_active(&self, package: &InstalledPackage) -> bool {
+        self.active_version(&package.name).is_some_and(|v| version::compare_versions(v, &package.version).is_eq())
+    }
+
+    pub fn set_active(&mut self, name: &str, version: Option<&str>) {
+        match version {
+            Some(v) => self.active.insert(name.to_string(), v.to_string()),
+            None => self.active.remove(name),
+        };
+    }
+
+    /// Names with an active version, sorted.
+    pub fn active_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.active.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    pub fn is_explicit(&self, name: &str) -> bool {
+        self.packages.values().any(|p| p.name == name && p.reason == InstallReason::Explicit)
+    }
+
+    /// Sets the install reason of every installed version of `name`.
+    pub fn set_reason(&mut self, name: &str, reason: InstallReason) {
+        for package in self.packages.values_mut().filter(|p| p.name == name) {
+            package.reason = reason;
+        }
+    }
 }
@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+
+use crate::constellation::{Constellation, PackageMetadata};
+use crate::error::{HpkgError, Result};
+use crate::version::{Version, VersionReq};
+
+/// Splits `name@constellation` into the package name and the pinned constellation.
+pub fn parse_package_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once('@') {
+        Some((name, constellation)) if !constellation.is_empty() => (name, Some(constellation)),
+        Some((name, _)) => (name, None),
+        None => (spec, None),
+    }
+}
+
+/// A package to resolve: a name, optionally pinned to a constellation and limited to the versions
+/// matching a constraint.
+#[derive(Debug, Clone)]
+pub struct PackageRequest {
+    pub name: String,
+    pub constellation: Option<String>,
+    pub version: Option<VersionReq>,
+}
+
+impl PackageRequest {
+    /// A request for `name` or `name@constellation`, any version.
+    pub fn from_spec(spec: &str) -> Self {
+        let (name, pin) = parse_package_spec(spec);
+        PackageRequest {
+            name: name.to_string(),
+            constellation: pin.map(str::to_string),
+            version: None,
+        }
+    }
+}
+
+/// Picks the candidate from the highest-priority constellation, then the newest version.
+/// A pinned constellation restricts the candidates to that constellation only.
+pub fn select_package<'a>(
+    available: &'a [PackageMetadata],
+    constellations: &[Constellation],
+    name: &str,
+    pinned_constellation: Option<&str>,
+) -> Option<&'a PackageMetadata> {
+    select_matching(available, constellations, name, pinned_constellation, None)
+}
+
+/// Like `select_package`, but only considers versions matching `version_req`.
+pub fn select_matching<'a>(
+    available: &'a [PackageMetadata],
+    constellations: &[Constellation],
+    name: &str,
+    pinned_constellation: Option<&str>,
+    version_req: Option<&VersionReq>,
+) -> Option<&'a PackageMetadata> {
+    let priority_of = |pkg: &PackageMetadata| {
+        constellations.iter()
+            .find(|c| c.name == pkg.constellation)
+            .map_or(0, |c| c.priority)
+    };
+
+    available.iter()
+        .filter(|p| p.name == name)
+        .filter(|p| pinned_constellation.is_none_or(|pin| p.constellation.eq_ignore_ascii_case(pin)))
+        .filter(|p| version_req.is_none_or(|req| req.matches(&p.version)))
+        .max_by_key(|p| (priority_of(p), Version::from(p.version.as_str())))
+}
+
+/// Resolves the requested package specs and all of their transitive dependencies.
+/// Requested packages must exist; missing dependencies are reported as warnings.
+/// Two specs pinning the same package to different constellations are a conflict.
+pub fn resolve(
+    available: &[PackageMetadata],
+    constellations: &[Constellation],
+    specs: &[&str],
+) -> Result<Vec<PackageMetadata>> {
+    let requests: Vec<PackageRequest> = specs.iter().map(|spec| PackageRequest::from_spec(spec)).collect();
+    resolve_requests(available, constellations, &requests)
+}
+
+/// Like `resolve`, for requests that may also constrain the version. Dependencies are resolved
+/// without constraints, but a dependency that is also requested uses the requested version.
+pub fn resolve_requests(
+    available: &[PackageMetadata],
+    constellations: &[Constellation],
+    requests: &[PackageRequest],
+) -> Result<Vec<PackageMetadata>> {
+    let mut resolved: Vec<PackageMetadata> = Vec::new();
+    let mut queue: VecDeque<PackageMetadata> = VecDeque::new();
+
+    for request in requests {
+        let (name, pin) = (request.name.as_str(), request.constellation.as_deref());
+        match select_matching(available, constellations, name, pin, request.version.as_ref()) {
+            Some(pkg) => {
+                check_pin_conflict(queue.iter(), name, pin, None)?;
+                queue.push_back(pkg.clone());
+            },
+            None => {
+                let location = match pin {
+                    Some(pin) => format!("constellation '{}'", pin),
+                    None => "any constellation".to_string(),
+                };
+                return Err(HpkgError::NotFound(match &request.version {
+                    Some(req) => format!("No version of '{}' matching '{}' found in {}.", name, req, location),
+                    None => format!("Package '{}' not found in {}.", name, location),
+                }));
+            }
+        }
+    }
+
+    while let Some(pkg) = queue.pop_front() {
+        if resolved.iter().any(|p| p.name == pkg.name) {
+            continue;
+        }
+
+        for dep_spec in pkg.dependencies.iter().flatten() {
+            let (dep_name, dep_pin) = parse_package_spec(dep_spec);
+            check_pin_conflict(resolved.iter().chain(queue.iter()), dep_name, dep_pin, Some(&pkg.name))?;
+            if resolved.iter().chain(queue.iter()).any(|p| p.name == dep_name) {
+                continue;
+            }
+            match select_package(available, constellations, dep_name, dep_pin) {
+                Some(dep_pkg) => queue.push_back(dep_pkg.clone()),
+                None => eprintln!("Warning: Dependency '{}' for '{}' not found in any constellation.", dep_name, pkg.name),
+            }
+        }
+
+        resolved.push(pkg);
+    }
+
+    Ok(resolved)
+}
+
+fn check_pin_conflict<'a>(
+    mut selected: impl Iterator<Item = &'a PackageMetadata>,
+    name: &str,
+    pin: Option<&str>,
+    required_by: Option<&str>,
+) -> Result<()> {
+    let (Some(pin), Some(existing)) = (pin, selected.find(|p| p.name == name)) else {
+        return Ok(());
+    };
+    if existing.constellation.eq_ignore_ascii_case(pin) {
+        return Ok(());
+    }
+
+    let requester = required_by.map_or(String::new(), |r| format!(" (required by '{}')", r));
+    Err(HpkgError::Conflict(format!(
+        "'{}@{}'{} conflicts with '{}' already selected from '{}'.",
+        name, pin, requester, name, existing.constellation
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constellation(name: &str, priority: i32) -> Constellation {
+        Constellation {
+            name: name.to_string(),
+            metadata_url: format!("https://example.org/{}.json", name),
+            mirrors: Vec::new(),
+            rank_mirrors: false,
+            priority,
+        }
+    }
+
+    fn package(name: &str, version: &str, constellation: &str) -> PackageMetadata {
+        PackageMetadata {
+            name: name.to_string(),
+            version: version.to_string(),
+            description: String::new(),
+            download_url: format!("https://example.org/{}-{}.tar.gz", name, version),
+            size_mb: 1,
+            installed_size_mb: None,
+            archive_type: "tar.gz".to_string(),
+            dependencies: None,
+            checksum: None,
+            license: None,
+            executables: None,
+            mirror_urls: Vec::new(),
+            constellation: constellation.to_string(),
+        }
+    }
+
+    #[test]
+    fn higher_priority_wins_over_newer_version() {
+        let constellations = [constellation("Hoshi Core", 10), constellation("Hoshi Extra", 0)];
+        let available = [
+            package("libstar", "1.2", "Hoshi Core"),
+            package("libstar", "1.10", "Hoshi Core"),
+            package("libstar", "3.0", "Hoshi Extra"),
+        ];
+
+        let selected = select_matching(&available, &constellations, "libstar", None, None).unwrap();
+        assert_eq!((selected.version.as_str(), selected.constellation.as_str()), ("1.10", "Hoshi Core"));
+    }
+
+    #[test]
+    fn release_is_newer_than_its_release_candidates() {
+        let constellations = [constellation("Hoshi Core", 0)];
+        let available = [
+            package("libstar", "2.0-rc1", "Hoshi Core"),
+            package("libstar", "2.0", "Hoshi Core"),
+            package("libstar", "2.0-rc2+build7", "Hoshi Core"),
+        ];
+
+        let selected = select_matching(&available, &constellations, "libstar", None, None).unwrap();
+        assert_eq!(selected.version, "2.0");
+    }
+
+    #[test]
+    fn pin_takes_precedence_over_priority() {
+        let constellations = [constellation("Hoshi Core", 10), constellation("Hoshi Extra", 0)];
+        let available = [
+            package("libstar", "1.2", "Hoshi Core"),
+            package("libstar", "0.9", "Hoshi Extra"),
+            package("libstar", "3.0", "Hoshi Extra"),
+        ];
+        let below_three: VersionReq = "<3".parse().unwrap();
+
+        let selected = select_matching(&available, &constellations, "libstar", Some("hoshi extra"), Some(&below_three)).unwrap();
+        assert_eq!((selected.version.as_str(), selected.constellation.as_str()), ("0.9", "Hoshi Extra"));
+        assert!(select_matching(&available, &constellations, "libstar", Some("Hoshi Nightly"), None).is_none());
+    }
+}
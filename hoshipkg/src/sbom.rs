@@ -0,0 +1,286 @@
+//! Software bills of materials for the installed packages, written by `hpkg sbom`.
+//!
+//! Both formats list every installed version with where its archive came from, its SHA-256 and
+//! its declared license, and relate each package to the installed versions of its dependencies.
+//! A dependency resolves to its active version, or the newest installed one when none is active.
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::license::LicenseExpr;
+use crate::registry::{InstallReason, InstalledPackage, PackageRegistry};
+use crate::timestamp;
+use crate::version;
+
+const TOOL_NAME: &str = "hpkg";
+const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SbomFormat {
+    /// SPDX 2.3 JSON
+    SpdxJson,
+    /// CycloneDX 1.5 JSON
+    CyclonedxJson,
+}
+
+impl SbomFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            SbomFormat::SpdxJson => "spdx-json",
+            SbomFormat::CyclonedxJson => "cyclonedx-json",
+        }
+    }
+}
+
+/// Builds the document for every package in `registry`, dated `created` (Unix seconds).
+pub fn generate(format: SbomFormat, registry: &PackageRegistry, created: u64) -> Value {
+    match format {
+        SbomFormat::SpdxJson => spdx(registry, created),
+        SbomFormat::CyclonedxJson => cyclonedx(registry, created),
+    }
+}
+
+/// An SPDX 2.3 document. The document describes the explicitly installed packages, which in
+/// turn depend on the rest.
+pub fn spdx(registry: &PackageRegistry, created: u64) -> Value {
+    let packages = sorted_packages(registry);
+
+    let spdx_packages: Vec<Value> = packages.iter().map(|package| {
+        let mut entry = json!({
+            "SPDXID": spdx_id(package),
+            "name": package.name,
+            "versionInfo": package.version,
+            "downloadLocation": package.source.as_ref().map_or("NOASSERTION", |s| s.download_url.as_str()),
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": declared_license(package).unwrap_or_else(|| "NOASSERTION".to_string()),
+            "copyrightText": "NOASSERTION",
+        });
+        if let Some(source) = &package.source {
+            entry["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": source.checksum }]);
+            entry["sourceInfo"] = json!(format!("From the {} constellation", source.constellation));
+        }
+        entry
+    }).collect();
+
+    let mut relationships = Vec::new();
+    for package in packages.iter().filter(|p| p.reason == InstallReason::Explicit) {
+        relationships.push(json!({
+            "spdxElementId": "SPDXRef-DOCUMENT",
+            "relationshipType": "DESCRIBES",
+            "relatedSpdxElement": spdx_id(package),
+        }));
+    }
+    for package in &packages {
+        for dependency in dependencies_of(registry, package) {
+            relationships.push(json!({
+                "spdxElementId": spdx_id(package),
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": spdx_id(dependency),
+            }));
+        }
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": "hoshi-installation",
+        "documentNamespace": format!("https://hoshi.invalid/spdx/installation-{}", fingerprint(&packages, created)),
+        "creationInfo": {
+            "created": timestamp::format_rfc3339(created),
+            "creators": [format!("Tool: {}-{}", TOOL_NAME, TOOL_VERSION)],
+        },
+        "packages": spdx_packages,
+        "relationships": relationships,
+    })
+}
+
+/// A CycloneDX 1.5 BOM with one `application` component per installed version.
+pub fn cyclonedx(registry: &PackageRegistry, created: u64) -> Value {
+    let packages = sorted_packages(registry);
+
+    let components: Vec<Value> = packages.iter().map(|package| {
+        let mut properties = vec![json!({ "name": "hoshi:reason", "value": reason_name(package) })];
+        let mut component = json!({
+            "type": "application",
+            "bom-ref": bom_ref(package),
+            "name": package.name,
+            "version": package.version,
+        });
+        if let Some(source) = &package.source {
+            component["hashes"] = json!([{ "alg": "SHA-256", "content": source.checksum }]);
+            component["externalReferences"] = json!([{ "type": "distribution", "url": source.download_url }]);
+            properties.push(json!({ "name": "hoshi:constellation", "value": source.constellation }));
+        }
+        if let Some(license) = declared_license(package) {
+            component["licenses"] = json!([{ "expression": license }]);
+        }
+        component["properties"] = Value::Array(properties);
+        component
+    }).collect();
+
+    let dependencies: Vec<Value> = packages.iter().map(|package| {
+        let depends_on: Vec<String> = dependencies_of(registry, package).into_iter().map(bom_ref).collect();
+        json!({ "ref": bom_ref(package), "dependsOn": depends_on })
+    }).collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp::format_rfc3339(created),
+            "tools": {
+                "components": [{ "type": "application", "name": TOOL_NAME, "version": TOOL_VERSION }],
+            },
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+fn sorted_packages(registry: &PackageRegistry) -> Vec<&InstalledPackage> {
+    let mut packages = registry.list_packages();
+    packages.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| version::compare_versions(&a.version, &b.version)));
+    packages
+}
+
+/// The installed versions `package`'s dependencies resolve to. Missing dependencies are left out.
+fn dependencies_of<'a>(registry: &'a PackageRegistry, package: &InstalledPackage) -> Vec<&'a InstalledPackage> {
+    package.dependencies.iter()
+        .filter_map(|name| {
+            let versions = registry.versions_of(name);
+            let active = versions.iter().find(|p| registry.is_active(p)).copied();
+            active.or_else(|| versions.last().copied())
+        })
+        .collect()
+}
+
+/// SPDX identifiers may only contain letters, digits, `.` and `-`.
+fn spdx_id(package: &InstalledPackage) -> String {
+    let sanitized: String = package.key().chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+        .collect();
+    format!("SPDXRef-Package-{}", sanitized)
+}
+
+/// The package's license expression in canonical form, or `None` if it has none or it doesn't parse.
+fn declared_license(package: &InstalledPackage) -> Option<String> {
+    let expr: LicenseExpr = package.license.as_deref()?.parse().ok()?;
+    Some(expr.to_string())
+}
+
+fn bom_ref(package: &InstalledPackage) -> String {
+    format!("{}@{}", package.name, package.version)
+}
+
+fn reason_name(package: &InstalledPackage) -> &'static str {
+    match package.reason {
+        InstallReason::Explicit => "explicit",
+        InstallReason::Dependency => "dependency",
+    }
+}
+
+/// A short hash of the package set and creation time, so every document gets its own namespace.
+fn fingerprint(packages: &[&InstalledPackage], created: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(created.to_be_bytes());
+    for package in packages {
+        hasher.update(package.key().as_bytes());
+        hasher.update([0]);
+        if let Some(source) = &package.source {
+            hasher.update(source.checksum.as_bytes());
+        }
+        hasher.update([0]);
+    }
+    hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::registry::PackageSource;
+
+    /// 2026-01-01T00:00:00Z
+    const CREATED: u64 = 1_767_225_600;
+    const COMET_CHECKSUM: &str = "b4cd07ff710c61265ede596087a849b4008c82298c8b8c55091d01fdcfa06cf3";
+    const LIBSTAR_CHECKSUM: &str = "ed7fda264b14942061232722841f73d091a30968e181b117cea80cecc3ad0d51";
+
+    fn installed(name: &str, version: &str, reason: InstallReason, dependencies: &[&str], license: Option<&str>, checksum: &str) -> InstalledPackage {
+        InstalledPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            install_path: PathBuf::from("/hoshi_packages").join(name).join(version),
+            installed_at: None,
+            reason,
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            files: Vec::new(),
+            executables: None,
+            source: Some(PackageSource {
+                constellation: "Hoshi Core".to_string(),
+                download_url: format!("https://example.org/{}-{}.tar.gz", name, version),
+                checksum: checksum.to_string(),
+            }),
+            license: license.map(str::to_string),
+        }
+    }
+
+    /// comet 2.1 depends on libstar, which is installed in 1.0 (active) and 1.2; libstar has no license.
+    fn registry() -> PackageRegistry {
+        let mut registry = PackageRegistry::default();
+        registry.add(installed("comet", "2.1", InstallReason::Explicit, &["libstar"], Some("MIT or Apache-2.0"), COMET_CHECKSUM));
+        registry.add(installed("libstar", "1.0", InstallReason::Dependency, &[], None, LIBSTAR_CHECKSUM));
+        registry.add(installed("libstar", "1.2", InstallReason::Dependency, &[], None, LIBSTAR_CHECKSUM));
+        registry.set_active("libstar", Some("1.0"));
+        registry
+    }
+
+    #[test]
+    fn spdx_lists_checksums_licenses_and_dependencies() {
+        let document = spdx(&registry(), CREATED);
+
+        assert_eq!(document["creationInfo"]["created"], "2026-01-01T00:00:00Z");
+        let packages = document["packages"].as_array().unwrap();
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0]["name"], "comet");
+        assert_eq!(packages[0]["checksums"][0]["algorithm"], "SHA256");
+        assert_eq!(packages[0]["checksums"][0]["checksumValue"], COMET_CHECKSUM);
+        assert_eq!(packages[0]["licenseDeclared"], "MIT OR Apache-2.0");
+        assert_eq!(packages[1]["licenseDeclared"], "NOASSERTION");
+
+        let relationships = document["relationships"].as_array().unwrap();
+        let depends_on: Vec<(&str, &str)> = relationships.iter()
+            .filter(|r| r["relationshipType"] == "DEPENDS_ON")
+            .map(|r| (r["spdxElementId"].as_str().unwrap(), r["relatedSpdxElement"].as_str().unwrap()))
+            .collect();
+        assert_eq!(depends_on, [(packages[0]["SPDXID"].as_str().unwrap(), packages[1]["SPDXID"].as_str().unwrap())]);
+        assert_eq!(packages[1]["versionInfo"], "1.0");
+    }
+
+    #[test]
+    fn cyclonedx_lists_checksums_licenses_and_dependencies() {
+        let document = cyclonedx(&registry(), CREATED);
+
+        assert_eq!(document["metadata"]["timestamp"], "2026-01-01T00:00:00Z");
+        let components = document["components"].as_array().unwrap();
+        assert_eq!(components[0]["hashes"][0], json!({ "alg": "SHA-256", "content": COMET_CHECKSUM }));
+        assert_eq!(components[0]["licenses"], json!([{ "expression": "MIT OR Apache-2.0" }]));
+        assert!(components[1].get("licenses").is_none());
+
+        let dependencies = document["dependencies"].as_array().unwrap();
+        assert_eq!(dependencies[0], json!({ "ref": "comet@2.1", "dependsOn": ["libstar@1.0"] }));
+        assert_eq!(dependencies[2], json!({ "ref": "libstar@1.2", "dependsOn": [] }));
+    }
+
+    #[test]
+    fn documents_depend_only_on_the_registry_and_creation_time() {
+        for format in [SbomFormat::SpdxJson, SbomFormat::CyclonedxJson] {
+            assert_eq!(generate(format, &registry(), CREATED), generate(format, &registry(), CREATED));
+        }
+        assert_ne!(spdx(&registry(), CREATED)["documentNamespace"], spdx(&registry(), CREATED + 1)["documentNamespace"]);
+    }
+}
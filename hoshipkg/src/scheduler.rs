@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Serialize;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task;
+
+use webfetch::{Client, DownloadProgress};
+
+use crate::cache::PackageCache;
+use crate::constellation::PackageMetadata;
+use crate::error::{HpkgError, Result};
+use crate::source;
+
+#[derive(Debug, Serialize)]
+pub struct DownloadedPackage {
+    pub package: PackageMetadata,
+    pub path: PathBuf,
+    pub served_by: String,
+}
+
+/// Downloads packages into the cache with at most `max_parallel` transfers in flight, sharing one HTTP client.
+/// Archives already cached with a matching checksum are reused without downloading.
+pub struct DownloadScheduler {
+    client: Client,
+    max_parallel: usize,
+}
+
+impl DownloadScheduler {
+    pub fn new(max_parallel: usize) -> Self {
+        DownloadScheduler {
+            client: Client::new(),
+            max_parallel: max_parallel.max(1),
+        }
+    }
+
+    pub fn archive_file_name(pkg: &PackageMetadata) -> String {
+        pkg.download_url.rsplit_once('/').map_or(
+            format!("{}-{}.archive", pkg.name, pkg.version),
+            |(_, name)| name.to_string()
+        )
+    }
+
+    pub async fn download_all(
+        &self,
+        packages: &[PackageMetadata],
+        cache: &PackageCache,
+    ) -> Result<Vec<DownloadedPackage>> {
+        let semaphore = Arc::new(Semaphore::new(self.max_parallel));
+        let multi = MultiProgress::new();
+
+        // Start from the advertised sizes; each bar corrects the total once the real length is known.
+        let estimated_total: u64 = packages.iter().map(|p| p.size_mb as u64 * 1024 * 1024).sum();
+        let overall = multi.add(ProgressBar::new(estimated_total));
+        overall.set_style(ProgressStyle::with_template(
+            "Total {spinner:.green} [{elapsed_precise}] [{bar:40.green/white}] {bytes}/{total_bytes} ({eta} remaining)"
+        ).unwrap());
+
+        let mut handles = Vec::new();
+        for pkg in packages {
+            let pkg = pkg.clone();
+            let client = self.client.clone();
+            let semaphore = semaphore.clone();
+            let multi = multi.clone();
+            let overall = overall.clone();
+            let cache = cache.clone();
+
+            handles.push(task::spawn(async move {
+                let estimate = pkg.size_mb as u64 * 1024 * 1024;
+                if let Some(path) = cache.lookup(&pkg).await {
+                    overall.set_length(overall.length().unwrap_or(0).saturating_sub(estimate));
+                    return Ok(DownloadedPackage { package: pkg, path, served_by: "cache".to_string() });
+                }
+
+                let _permit = semaphore.acquire_owned().await
+                    .map_err(|e| HpkgError::Io(format!("download scheduler closed: {}", e)))?;
+
+                let pb = multi.insert_before(&overall, ProgressBar::new(0));
+                pb.set_style(ProgressStyle::with_template(
+                    "{msg} {spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes_per_sec} {bytes}/{total_bytes} ({eta})"
+                ).unwrap());
+                pb.set_message(format!("{} v{}", pkg.name, pkg.version));
+
+                let (tx, rx) = mpsc::channel::<DownloadProgress>(100);
+                let pb_handle = task::spawn(track_progress(rx, pb, overall, estimate, pkg.name.clone(), pkg.version.clone()));
+
+                let urls = if pkg.mirror_urls.is_empty() {
+                    vec![pkg.download_url.clone()]
+                } else {
+                    pkg.mirror_urls.clone()
+                };
+                let file_name = Self::archive_file_name(&pkg);
+                let result = source::download_from_mirrors(
+                    &client,
+                    &urls,
+                    pkg.checksum.as_deref(),
+                    &cache.entry_dir(&pkg),
+                    &file_name,
+                    tx,
+                ).await;
+                let _ = pb_handle.await;
+
+                let (path, served_by) = result?;
+                Ok::<_, HpkgError>(DownloadedPackage { package: pkg, path, served_by })
+            }));
+        }
+
+        let mut downloaded = Vec::new();
+        let mut first_error = None;
+        for handle in handles {
+            match handle.await? {
+                Ok(pkg) => downloaded.push(pkg),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        overall.finish_with_message("All downloads finished.");
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(downloaded),
+        }
+    }
+}
+
+async fn track_progress(
+    mut rx: mpsc::Receiver<DownloadProgress>,
+    pb: ProgressBar,
+    overall: ProgressBar,
+    estimate: u64,
+    name: String,
+    version: String,
+) {
+    let mut counted_total = estimate;
+    let mut counted_bytes = 0;
+
+    while let Some(progress) = rx.recv().await {
+        if let Some(total) = progress.total_bytes {
+            if pb.length() != Some(total) {
+                pb.set_length(total);
+            }
+            if total != counted_total {
+                let overall_total = overall.length().unwrap_or(0);
+                overall.set_length((overall_total + total).saturating_sub(counted_total));
+                counted_total = total;
+            }
+        }
+        pb.set_position(progress.current_bytes);
+
+        // A mirror retry restarts from zero, so the overall position can move backwards too.
+        if progress.current_bytes >= counted_bytes {
+            overall.inc(progress.current_bytes - counted_bytes);
+        } else {
+            overall.set_position(overall.position().saturating_sub(counted_bytes - progress.current_bytes));
+        }
+        counted_bytes = progress.current_bytes;
+
+        if progress.done {
+            pb.finish_with_message(format!("{} v{} downloaded.", name, version));
+        }
+    }
+    pb.finish();
+}
@@ -0,0 +1,220 @@
+//! Copies of the registry and of the install directories a transaction is about to change,
+//! taken before it runs. Files are hardlinked where possible and copied otherwise.
+//!
+//! Each snapshot lives in `<root>/<id>/`: `snapshot.json` describes it, `registry.json` is the
+//! registry as it was, and `files/<n>` holds the n-th recorded path.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::config::HoshiConfig;
+use crate::error::{HpkgError, Result};
+use crate::history;
+use crate::timestamp;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnapshotPath {
+    pub path: PathBuf,
+    /// False for directories the transaction was about to create; restoring removes them.
+    pub existed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Snapshot {
+    pub id: u64,
+    /// Unix seconds.
+    pub timestamp: u64,
+    /// The command line of the transaction the snapshot was taken for.
+    pub command: String,
+    /// False if there was no registry yet; restoring then removes it.
+    pub had_registry: bool,
+    pub paths: Vec<SnapshotPath>,
+}
+
+pub struct SnapshotStore {
+    root: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn get_default_dir() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("hoshi")
+            .join("snapshots")
+    }
+
+    pub fn from_config(config: &HoshiConfig) -> Self {
+        SnapshotStore {
+            root: config.snapshot_dir.clone().unwrap_or_else(Self::get_default_dir),
+        }
+    }
+
+    fn dir(&self, id: u64) -> PathBuf {
+        self.root.join(id.to_string())
+    }
+
+    /// Every complete snapshot, oldest first.
+    pub async fn list(&self) -> Result<Vec<Snapshot>> {
+        let mut snapshots = Vec::new();
+        if !self.root.exists() {
+            return Ok(snapshots);
+        }
+
+        let mut read_dir = tokio::fs::read_dir(&self.root).await?;
+        while let Some(entry) = read_dir.next_entry().await? {
+            let metadata_path = entry.path().join("snapshot.json");
+            // A directory without metadata is a snapshot that was interrupted while being taken.
+            if !metadata_path.exists() {
+                continue;
+            }
+            let content = tokio::fs::read_to_string(&metadata_path).await?;
+            let snapshot: Snapshot = serde_json::from_str(&content)
+                .map_err(|e| HpkgError::Parse(format!("Snapshot {} is corrupted: {}", metadata_path.display(), e)))?;
+            snapshots.push(snapshot);
+        }
+        snapshots.sort_by_key(|s| s.id);
+        Ok(snapshots)
+    }
+
+    pub async fn get(&self, id: u64) -> Result<Snapshot> {
+        self.list().await?
+            .into_iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| HpkgError::NotFound(format!("No snapshot with id {}.", id)))
+    }
+
+    /// Snapshots the registry at `registry_path` and every directory in `paths`.
+    pub async fn create(&self, registry_path: &Path, paths: &[PathBuf]) -> Result<Snapshot> {
+        let id = self.next_id().await?;
+        let dir = self.dir(id);
+        tokio::fs::create_dir_all(dir.join("files")).await
+            .map_err(|e| HpkgError::Io(format!("Failed to create snapshot {}: {}", dir.display(), e)))?;
+
+        let had_registry = registry_path.exists();
+        if had_registry {
+            tokio::fs::copy(registry_path, dir.join("registry.json")).await
+                .map_err(|e| HpkgError::Io(format!("Failed to snapshot registry {}: {}", registry_path.display(), e)))?;
+        }
+
+        let mut snapshot_paths = Vec::new();
+        for (i, path) in paths.iter().enumerate() {
+            let existed = path.exists();
+            if existed {
+                link_tree(path, &dir.join("files").join(i.to_string())).await
+                    .map_err(|e| HpkgError::Io(format!("Failed to snapshot {}: {}", path.display(), e)))?;
+            }
+            snapshot_paths.push(SnapshotPath { path: path.clone(), existed });
+        }
+
+        let snapshot = Snapshot {
+            id,
+            timestamp: timestamp::now(),
+            command: history::command_line(),
+            had_registry,
+            paths: snapshot_paths,
+        };
+        let content = serde_json::to_string_pretty(&snapshot)?;
+        tokio::fs::write(dir.join("snapshot.json"), content).await
+            .map_err(|e| HpkgError::Io(format!("Failed to write snapshot {}: {}", dir.display(), e)))?;
+        Ok(snapshot)
+    }
+
+    /// Puts the registry and every recorded directory back as they were. The snapshot is kept.
+    pub async fn restore(&self, snapshot: &Snapshot, registry_path: &Path) -> Result<()> {
+        let dir = self.dir(snapshot.id);
+
+        for (i, recorded) in snapshot.paths.iter().enumerate() {
+            match tokio::fs::remove_dir_all(&recorded.path).await {
+                Ok(()) => {},
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+                Err(e) => return Err(HpkgError::Io(format!("Failed to remove {}: {}", recorded.path.display(), e))),
+            }
+            if recorded.existed {
+                link_tree(&dir.join("files").join(i.to_string()), &recorded.path).await
+                    .map_err(|e| HpkgError::Io(format!("Failed to restore {}: {}", recorded.path.display(), e)))?;
+            }
+        }
+
+        if snapshot.had_registry {
+            tokio::fs::copy(dir.join("registry.json"), registry_path).await
+                .map_err(|e| HpkgError::Io(format!("Failed to restore registry {}: {}", registry_path.display(), e)))?;
+        } else if registry_path.exists() {
+            tokio::fs::remove_file(registry_path).await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes all but the newest `keep` snapshots. Returns the removed ids.
+    pub async fn prune(&self, keep: usize) -> Result<Vec<u64>> {
+        let snapshots = self.list().await?;
+        let excess = snapshots.len().saturating_sub(keep);
+        let mut removed = Vec::new();
+        for snapshot in &snapshots[..excess] {
+            tokio::fs::remove_dir_all(self.dir(snapshot.id)).await?;
+            removed.push(snapshot.id);
+        }
+        Ok(removed)
+    }
+
+    async fn next_id(&self) -> Result<u64> {
+        if !self.root.exists() {
+            return Ok(1);
+        }
+        let mut max_id = 0;
+        let mut read_dir = tokio::fs::read_dir(&self.root).await?;
+        while let Some(entry) = read_dir.next_entry().await? {
+            if let Some(id) = entry.file_name().to_str().and_then(|n| n.parse::<u64>().ok()) {
+                max_id = max_id.max(id);
+            }
+        }
+        Ok(max_id + 1)
+    }
+}
+
+/// Takes a snapshot before a transaction and prunes old ones, unless `max_snapshots` is 0.
+pub async fn take(config: &HoshiConfig, registry_path: &Path, paths: &[PathBuf]) -> Result<Option<Snapshot>> {
+    if config.max_snapshots == 0 {
+        return Ok(None);
+    }
+    let store = SnapshotStore::from_config(config);
+    let snapshot = store.create(registry_path, paths).await?;
+    store.prune(config.max_snapshots).await?;
+    Ok(Some(snapshot))
+}
+
+/// Recreates `src` at `dst`, hardlinking files and falling back to copies across filesystems.
+async fn link_tree(src: &Path, dst: &Path) -> io::Result<()> {
+    let src = src.to_path_buf();
+    let dst = dst.to_path_buf();
+    tokio::task::spawn_blocking(move || link_tree_blocking(&src, &dst))
+        .await
+        .map_err(io::Error::other)?
+}
+
+fn link_tree_blocking(src: &Path, dst: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = dst.join(entry.file_name());
+        if file_type.is_dir() {
+            link_tree_blocking(&entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &target)?;
+        } else if std::fs::hard_link(entry.path(), &target).is_err() {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(src: &Path, dst: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(std::fs::read_link(src)?, dst)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(src: &Path, dst: &Path) -> io::Result<()> {
+    std::fs::copy(src, dst).map(|_| ())
+}
@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+use webfetch::{Client, DownloadProgress};
+
+use crate::checksum;
+use crate::error::{HpkgError, Result};
+
+pub use hoshi_core::fetch::{is_remote, local_path, measure_latency, rank_by_latency, resolve_location};
+
+pub async fn fetch_to_string(location: &str) -> Result<String> {
+    Ok(hoshi_core::fetch::fetch_to_string(location).await?)
+}
+
+/// Downloads a remote archive through webfetch, or copies a local one into `target_dir`.
+pub async fn download_with_progress(
+    client: &Client,
+    location: &str,
+    target_dir: &Path,
+    file_name: &str,
+    tx: mpsc::Sender<DownloadProgress>,
+) -> Result<PathBuf> {
+    let Some(source_path) = local_path(location) else {
+        return webfetch::download_file_with_client(client, location, target_dir, file_name, tx).await
+            .map_err(|e| HpkgError::Network(e.to_string()));
+    };
+
+    tokio::fs::create_dir_all(target_dir).await?;
+    let file_path = target_dir.join(file_name);
+    let copied = tokio::fs::copy(&source_path, &file_path).await
+        .map_err(|e| HpkgError::Io(format!("{}: {}", source_path.display(), e)))?;
+
+    let _ = tx.send(DownloadProgress {
+        current_bytes: copied,
+        total_bytes: Some(copied),
+        done: true,
+    }).await;
+
+    Ok(file_path)
+}
+
+/// Downloads from the first location that succeeds and matches `expected_checksum`.
+/// Returns the downloaded file and the location that served it.
+pub async fn download_from_mirrors(
+    client: &Client,
+    locations: &[String],
+    expected_checksum: Option<&str>,
+    target_dir: &Path,
+    file_name: &str,
+    tx: mpsc::Sender<DownloadProgress>,
+) -> Result<(PathBuf, String)> {
+    let mut last_error: Option<HpkgError> = None;
+
+    for location in locations {
+        // Each attempt reports through its own channel so a failed mirror doesn't mark the download as done.
+        let (attempt_tx, mut attempt_rx) = mpsc::channel::<DownloadProgress>(100);
+        let forward_tx = tx.clone();
+        let forwarder = tokio::spawn(async move {
+            while let Some(progress) = attempt_rx.recv().await {
+                if !progress.done {
+                    let _ = forward_tx.send(progress).await;
+                }
+            }
+        });
+
+        let result = download_with_progress(client, location, target_dir, file_name, attempt_tx).await;
+        let _ = forwarder.await;
+
+        let file_path = match result {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("Mirror {} failed: {}", location, e);
+                last_error = Some(e);
+                continue;
+            }
+        };
+
+        if let Some(expected) = expected_checksum {
+            let actual = checksum::sha256_file(&file_path).await?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                eprintln!("Checksum mismatch from {}: expected {}, got {}", location, expected, actual);
+                let _ = tokio::fs::remove_file(&file_path).await;
+                last_error = Some(HpkgError::Network(format!("checksum mismatch from {}", location)));
+                continue;
+            }
+        }
+
+        let size = tokio::fs::metadata(&file_path).await?.len();
+        let _ = tx.send(DownloadProgress {
+            current_bytes: size,
+            total_bytes: Some(size),
+            done: true,
+        }).await;
+
+        return Ok((file_path, location.clone()));
+    }
+
+    Err(last_error.unwrap_or_else(|| HpkgError::NotFound("no download locations available".to_string())))
+}
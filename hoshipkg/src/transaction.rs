@@ -0,0 +1,315 @@
+//! The transaction engine: plans, downloads and installs a resolved set of packages.
+
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+
+use crate::activation::{self, LinkReport};
+use crate::cache::PackageCache;
+use crate::checksum;
+use crate::constellation::PackageMetadata;
+use crate::diskspace;
+use crate::error::{HpkgError, Result};
+use crate::history::{ChangeAction, PackageChange};
+use crate::manifest;
+use crate::plan::{InstallPlan, PlanRemoval};
+use crate::registry::{InstallReason, InstalledPackage, PackageRegistry, PackageSource};
+use crate::resolver;
+use crate::scheduler::{DownloadScheduler, DownloadedPackage};
+use crate::status;
+use crate::timestamp;
+
+#[derive(Debug, Serialize)]
+pub struct FailedPackage {
+    pub name: String,
+    pub version: String,
+    pub error: String,
+}
+
+/// What `Transaction::install` did to each downloaded package.
+#[derive(Debug, Default, Serialize)]
+pub struct InstallOutcome {
+    pub installed: Vec<InstalledPackage>,
+    pub failed: Vec<FailedPackage>,
+}
+
+/// Fails with `Io` when any package failed to install, so the command exits non-zero. Call it
+/// after saving the registry and history and emitting the report, so what did install is kept.
+pub fn ensure_installed(failed: &[FailedPackage], summary: &str) -> Result<()> {
+    if failed.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<String> = failed.iter().map(|f| format!("{} v{}", f.name, f.version)).collect();
+    Err(HpkgError::Io(format!("{}; failed to install {}.", summary, names.join(", "))))
+}
+
+/// A resolved set of packages and the plan for installing them under `install_root`.
+/// Packages are installed to `<install_root>/<name>/<version>`.
+pub struct Transaction {
+    packages: Vec<PackageMetadata>,
+    /// Names the user asked for; everything else in `packages` is a dependency.
+    explicit: Vec<String>,
+    plan: InstallPlan,
+    cache: PackageCache,
+    install_root: PathBuf,
+}
+
+impl Transaction {
+    pub fn default_install_root() -> PathBuf {
+        PathBuf::from("./hoshi_packages")
+    }
+
+    /// Computes the plan against the current registry and cache; nothing is touched yet.
+    /// `explicit` names the packages the user asked for, as opposed to their dependencies.
+    pub fn new(
+        packages: Vec<PackageMetadata>,
+        explicit: Vec<String>,
+        registry: &PackageRegistry,
+        cache: PackageCache,
+        install_root: PathBuf,
+    ) -> Self {
+        let plan = InstallPlan::new(&packages, registry, &cache);
+        Transaction {
+            packages,
+            explicit,
+            plan,
+            cache,
+            install_root,
+        }
+    }
+
+    /// Adds installed versions the caller will uninstall after `install`, so the plan shows them.
+    pub fn with_removals(mut self, removals: Vec<PlanRemoval>) -> Self {
+        self.plan.removals = removals;
+        self
+    }
+
+    pub fn packages(&self) -> &[PackageMetadata] {
+        &self.packages
+    }
+
+    pub fn plan(&self) -> &InstallPlan {
+        &self.plan
+    }
+
+    pub fn cache(&self) -> &PackageCache {
+        &self.cache
+    }
+
+    pub fn install_root(&self) -> &Path {
+        &self.install_root
+    }
+
+    /// The install directories `install` will replace, whether or not they exist yet, and those of
+    /// the planned removals.
+    pub async fn affected_paths(&self, registry: &PackageRegistry) -> Result<Vec<PathBuf>> {
+        let install_root = prepare_install_root(&self.install_root).await?;
+        let mut paths: Vec<PathBuf> = self.packages.iter().map(|p| install_root.join(&p.name).join(&p.version)).collect();
+        paths.extend(self.plan.removals.iter()
+            .filter_map(|r| registry.versions_of(&r.name).into_iter().find(|p| p.version == r.version))
+            .map(|p| p.install_path.clone()));
+        Ok(paths)
+    }
+
+    /// The history record for what `install` did, using the plan to tell installs from upgrades.
+    pub fn changes(&self, outcome: &InstallOutcome) -> Vec<PackageChange> {
+        outcome.installed.iter()
+            .map(|installed| {
+                let entry = self.plan.installs.iter().find(|e| e.name == installed.name && e.version == installed.version);
+                PackageChange {
+                    name: installed.name.clone(),
+                    action: entry.map_or(ChangeAction::Install, |e| e.action.into()),
+                    version: installed.version.clone(),
+                    previous_version: entry.and_then(|e| e.installed_version.clone()),
+                    reason: installed.reason,
+                    dependencies: installed.dependencies.clone(),
+                    source: installed.source.clone(),
+                    license: installed.license.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Fails with `InsufficientSpace` if the downloads, and the extracted packages unless
+    /// `download_only` is set, won't fit on their filesystems.
+    pub fn check_space(&self, download_only: bool) -> Result<()> {
+        let mut requirements = vec![(self.cache.root(), self.plan.download_bytes)];
+        if !download_only {
+            requirements.push((self.install_root.as_path(), self.plan.installed_bytes));
+        }
+        diskspace::ensure_space(&requirements)
+    }
+
+    /// Fetches every archive into the cache, reusing verified cached copies.
+    pub async fn download(&self, scheduler: &DownloadScheduler) -> Result<Vec<DownloadedPackage>> {
+        scheduler.download_all(&self.packages, &self.cache).await
+    }
+
+    /// Extracts the downloaded archives, records them in `registry` and makes them the active versions.
+    /// A package stays explicit once the user has asked for it, even when a later transaction only
+    /// pulls it in as a dependency.
+    /// A package that fails to install is reported in the outcome and skipped; saving the registry is up to the caller.
+    pub async fn install(&self, downloaded: &[DownloadedPackage], registry: &mut PackageRegistry) -> Result<InstallOutcome> {
+        let install_root = prepare_install_root(&self.install_root).await?;
+
+        let mut outcome = InstallOutcome::default();
+        for downloaded in downloaded {
+            let pkg = &downloaded.package;
+            let reason = if self.explicit.contains(&pkg.name) || registry.is_explicit(&pkg.name) {
+                InstallReason::Explicit
+            } else {
+                InstallReason::Dependency
+            };
+            let dependencies = pkg.dependencies.iter().flatten()
+                .map(|spec| resolver::parse_package_spec(spec).0.to_string())
+                .collect();
+
+            match install_downloaded(downloaded, &install_root, reason, dependencies).await {
+                Ok(installed) => {
+                    registry.add(installed.clone());
+                    activate(registry, &installed.name, &installed.version).await;
+                    outcome.installed.push(installed);
+                },
+                Err(e) => {
+                    eprintln!("Error installing {}: {}", pkg.name, e);
+                    outcome.failed.push(FailedPackage {
+                        name: pkg.name.clone(),
+                        version: pkg.version.clone(),
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Uninstalls the planned removals once `install` has run. A package that failed to install
+    /// keeps the versions it had, so it is never left with none.
+    pub async fn uninstall_removals(&self, registry: &mut PackageRegistry, outcome: &InstallOutcome) -> Result<Vec<InstalledPackage>> {
+        let mut removed = Vec::new();
+        for removal in &self.plan.removals {
+            if outcome.failed.iter().any(|f| f.name == removal.name) {
+                status!("Keeping {} v{} because its replacement failed to install.", removal.name, removal.version);
+                continue;
+            }
+            if let Some((package, _)) = uninstall(registry, &removal.name, &removal.version).await? {
+                status!("Removed {} v{}", package.name, package.version);
+                removed.push(package);
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Installs a downloaded archive and records where it came from.
+async fn install_downloaded(
+    downloaded: &DownloadedPackage,
+    install_root: &Path,
+    reason: InstallReason,
+    dependencies: Vec<String>,
+) -> Result<InstalledPackage> {
+    let pkg = &downloaded.package;
+    // Archives without a published checksum are hashed so the install can still be locked.
+    let checksum = match &pkg.checksum {
+        Some(checksum) => checksum.to_ascii_lowercase(),
+        None => checksum::sha256_file(&downloaded.path).await?,
+    };
+    let mut installed = install_archive(
+        &downloaded.path, install_root, &pkg.name, &pkg.version, reason, dependencies, pkg.executables.as_deref(),
+    ).await?;
+    installed.source = Some(PackageSource {
+        constellation: pkg.constellation.clone(),
+        download_url: pkg.download_url.clone(),
+        checksum,
+    });
+    installed.license = pkg.license.clone();
+    Ok(installed)
+}
+
+/// Makes a freshly installed version the active one. A failure leaves the package installed but
+/// inactive, so it is reported rather than failing the install.
+pub async fn activate(registry: &mut PackageRegistry, name: &str, version: &str) {
+    match activation::activate(registry, name, version).await {
+        Ok(report) => {
+            for conflict in &report.conflicts {
+                status!("Not linking {}: another package already provides it.", conflict.display());
+            }
+        },
+        Err(e) => eprintln!("Warning: could not make {} v{} active: {}", name, version, e),
+    }
+}
+
+/// Creates `install_root` if needed and returns it canonicalized, so recorded paths stay valid
+/// no matter which directory later commands run from.
+pub async fn prepare_install_root(install_root: &Path) -> Result<PathBuf> {
+    tokio::fs::create_dir_all(install_root).await
+        .map_err(|e| HpkgError::Io(format!("Failed to create {}: {}", install_root.display(), e)))?;
+    Ok(tokio::fs::canonicalize(install_root).await?)
+}
+
+/// Extracts `archive` to `<install_root>/<name>/<version>` and returns the registry entry describing it.
+/// `install_root` should come from `prepare_install_root`. `executables` are the ones the package
+/// metadata declares; without them they are discovered from the extracted files.
+pub async fn install_archive(
+    archive: &Path,
+    install_root: &Path,
+    name: &str,
+    version: &str,
+    reason: InstallReason,
+    dependencies: Vec<String>,
+    executables: Option<&[String]>,
+) -> Result<InstalledPackage> {
+    let install_dir = install_root.join(name).join(version);
+    status!("Extracting {} to {}...", name, install_dir.display());
+
+    // Start from an empty directory so stale files don't linger, and so files hardlinked
+    // into a snapshot are replaced rather than rewritten in place.
+    remove_dir_if_present(&install_dir).await?;
+
+    kaika::extract_archive(archive, &install_dir).await
+        .map_err(|e| HpkgError::Io(format!("Failed to extract {}: {}", archive.display(), e)))?;
+    status!("Extracted: {}", name);
+
+    let files = manifest::scan(&install_dir).await
+        .map_err(|e| HpkgError::Io(format!("Failed to list files of {}: {}", name, e)))?;
+    let executables = match executables {
+        Some(declared) => activation::declared_executables(name, declared, &files),
+        None => activation::discover_executables(&files),
+    };
+
+    Ok(InstalledPackage {
+        name: name.to_string(),
+        version: version.to_string(),
+        install_path: install_dir,
+        installed_at: Some(timestamp::now()),
+        reason,
+        dependencies,
+        files,
+        executables: Some(executables),
+        source: None,
+        license: None,
+    })
+}
+
+/// Removes one installed version from `registry` and deletes its install directory, and the
+/// package's directory once no version is left. If it was the active version, the newest remaining
+/// one becomes active; the returned report says which links changed.
+pub async fn uninstall(registry: &mut PackageRegistry, name: &str, version: &str) -> Result<Option<(InstalledPackage, LinkReport)>> {
+    let Some(removed) = registry.remove(name, Some(version)) else {
+        return Ok(None);
+    };
+    remove_dir_if_present(&removed.install_path).await?;
+    let links = activation::on_removed(registry, &removed).await?;
+    if let Some(package_dir) = removed.install_path.parent() {
+        // Fails harmlessly while other versions are still in there.
+        let _ = tokio::fs::remove_dir(package_dir).await;
+    }
+    Ok(Some((removed, links)))
+}
+
+async fn remove_dir_if_present(dir: &Path) -> Result<()> {
+    match tokio::fs::remove_dir_all(dir).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(HpkgError::Io(format!("Failed to remove {}: {}", dir.display(), e))),
+    }
+}
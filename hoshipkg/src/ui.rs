@@ -0,0 +1,97 @@
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use clap::ValueEnum;
+use dialoguer::Confirm;
+use serde::Serialize;
+
+use crate::error::HpkgError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Flags shared by every command.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    pub assume_yes: bool,
+    pub dry_run: bool,
+    pub output: OutputFormat,
+}
+
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+static EMITTED_JSON: AtomicBool = AtomicBool::new(false);
+
+pub fn init(options: &Options) {
+    let _ = OUTPUT_FORMAT.set(options.output);
+}
+
+pub fn is_json() -> bool {
+    OUTPUT_FORMAT.get() == Some(&OutputFormat::Json)
+}
+
+/// Human-readable progress goes to stderr in JSON mode, so stdout only ever carries the JSON document.
+pub fn print_status(args: fmt::Arguments) {
+    if is_json() {
+        eprintln!("{}", args);
+    } else {
+        println!("{}", args);
+    }
+}
+
+/// Prints a progress line through `print_status`, with `format!` syntax.
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        $crate::ui::print_status(format_args!($($arg)*))
+    };
+}
+
+pub fn emit_json<T: Serialize>(value: &T) {
+    EMITTED_JSON.store(true, Ordering::Relaxed);
+    println!("{}", serde_json::to_string_pretty(value).expect("Failed to serialize output"));
+}
+
+/// Prints a failed command's error. In JSON mode it goes to stdout as
+/// `{"error": {"code": ..., "exit_code": ..., "message": ...}}`, or to stderr when the
+/// command already printed its own report, so stdout never carries two documents.
+pub fn report_error(error: &HpkgError) {
+    if is_json() {
+        let document = serde_json::json!({
+            "error": {
+                "code": error.code(),
+                "exit_code": error.exit_code(),
+                "message": error.to_string(),
+            }
+        });
+        if EMITTED_JSON.load(Ordering::Relaxed) {
+            eprintln!("{}", document);
+        } else {
+            emit_json(&document);
+        }
+    } else {
+        eprintln!("Error: {}", error);
+    }
+}
+
+/// Asks for confirmation, or answers yes with `--yes`.
+/// Without a terminal to ask on, refuses instead of blocking or panicking.
+pub fn confirm(prompt: &str, options: &Options) -> bool {
+    if options.assume_yes {
+        return true;
+    }
+
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        eprintln!("{} Not running in an interactive terminal, pass --yes to proceed.", prompt);
+        return false;
+    }
+
+    Confirm::new()
+        .with_prompt(prompt)
+        .interact()
+        .unwrap_or(false)
+}
@@ -0,0 +1,271 @@
+//! The world file: the packages an installation should have, for `hpkg apply`.
+//!
+//! Each entry is either a spec (`name` or `name@constellation`) or an object that can also
+//! constrain the version:
+//!
+//! ```json
+//! { "packages": [
+//!     "world",
+//!     "hello@Hoshi Core",
+//!     { "name": "libstar", "version": ">=1.2, <2", "constellation": "Hoshi Extra" }
+//! ] }
+//! ```
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::{Deserialize, Serialize};
+
+use crate::constellation::PackageMetadata;
+use crate::error::{HpkgError, Result};
+use crate::plan::PlanRemoval;
+use crate::registry::{InstallReason, InstalledPackage, PackageRegistry};
+use crate::resolver::{self, PackageRequest};
+use crate::version::{self, VersionReq};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldEntry {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<VersionReq>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constellation: Option<String>,
+}
+
+/// The object form of an entry.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FullEntry {
+    name: String,
+    #[serde(default)]
+    version: Option<VersionReq>,
+    #[serde(default)]
+    constellation: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for WorldEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct EntryVisitor;
+
+        impl<'de> Visitor<'de> for EntryVisitor {
+            type Value = WorldEntry;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a package spec or an object with a name")
+            }
+
+            fn visit_str<E: de::Error>(self, spec: &str) -> std::result::Result<WorldEntry, E> {
+                let request = PackageRequest::from_spec(spec);
+                Ok(WorldEntry { name: request.name, version: None, constellation: request.constellation })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> std::result::Result<WorldEntry, A::Error> {
+                let full = FullEntry::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                Ok(WorldEntry { name: full.name, version: full.version, constellation: full.constellation })
+            }
+        }
+
+        deserializer.deserialize_any(EntryVisitor)
+    }
+}
+
+impl WorldEntry {
+    pub fn request(&self) -> PackageRequest {
+        PackageRequest {
+            name: self.name.clone(),
+            constellation: self.constellation.clone(),
+            version: self.version.clone(),
+        }
+    }
+
+    /// Whether `package` satisfies the entry. A pinned entry is only satisfied by a version recorded
+    /// as coming from that constellation.
+    fn allows(&self, package: &InstalledPackage) -> bool {
+        let version_matches = self.version.as_ref().is_none_or(|req| req.matches(&package.version));
+        let constellation_matches = self.constellation.as_ref().is_none_or(|pin| {
+            package.source.as_ref().is_some_and(|source| source.constellation.eq_ignore_ascii_case(pin))
+        });
+        version_matches && constellation_matches
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorldFile {
+    pub packages: Vec<WorldEntry>,
+}
+
+impl WorldFile {
+    pub fn get_default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("hoshi")
+            .join("world.json")
+    }
+
+    pub async fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(HpkgError::NotFound(format!("No world file at {}.", path.display())));
+        }
+        let content = tokio::fs::read_to_string(path).await
+            .map_err(|e| HpkgError::Io(format!("Failed to read world file {}: {}", path.display(), e)))?;
+        let world: WorldFile = serde_json::from_str(&content)
+            .map_err(|e| HpkgError::Parse(format!("Invalid world file {}: {}", path.display(), e)))?;
+
+        for (i, entry) in world.packages.iter().enumerate() {
+            if world.packages[..i].iter().any(|e| e.name == entry.name) {
+                return Err(HpkgError::Conflict(format!("'{}' is listed more than once in {}.", entry.name, path.display())));
+            }
+        }
+        Ok(world)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&WorldEntry> {
+        self.packages.iter().find(|e| e.name == name)
+    }
+}
+
+/// What has to change for the registry to match a world file.
+#[derive(Debug, Default, Serialize)]
+pub struct WorldDiff {
+    /// Entries without an installed version that satisfies them.
+    pub missing: Vec<WorldEntry>,
+    /// Installed versions of unlisted explicit packages, and of listed packages outside their
+    /// constraint or from a constellation other than their pin.
+    pub removals: Vec<PlanRemoval>,
+    /// Listed packages that are installed only as dependencies.
+    pub mark_explicit: Vec<String>,
+    /// Unlisted explicit packages that are kept because something that stays depends on them.
+    pub mark_dependency: Vec<String>,
+}
+
+impl WorldDiff {
+    pub fn new(world: &WorldFile, registry: &PackageRegistry) -> Self {
+        let mut diff = WorldDiff::default();
+
+        for entry in &world.packages {
+            let installed = registry.versions_of(&entry.name);
+            if !installed.iter().any(|p| entry.allows(p)) {
+                diff.missing.push(entry.clone());
+            } else if !registry.is_explicit(&entry.name) {
+                diff.mark_explicit.push(entry.name.clone());
+            }
+            for pkg in installed.iter().filter(|p| !entry.allows(p)) {
+                diff.removals.push(PlanRemoval { name: pkg.name.clone(), version: pkg.version.clone() });
+            }
+        }
+
+        let mut packages = registry.list_packages();
+        packages.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| version::compare_versions(&a.version, &b.version)));
+        for pkg in packages {
+            if pkg.reason == InstallReason::Explicit && world.get(&pkg.name).is_none() {
+                diff.removals.push(PlanRemoval { name: pkg.name.clone(), version: pkg.version.clone() });
+            }
+        }
+        diff
+    }
+
+    /// Drops removals of versions that `incoming` reinstalls in place, such as the same version
+    /// from the pinned constellation.
+    pub fn drop_replaced(&mut self, incoming: &[PackageMetadata]) {
+        self.removals.retain(|r| !incoming.iter()
+            .any(|p| p.name == r.name && version::compare_versions(&p.version, &r.version).is_eq()));
+    }
+
+    /// Keeps unlisted packages that a remaining package or one of `incoming` still depends on,
+    /// demoting them to dependencies instead of removing them.
+    pub fn keep_required(&mut self, world: &WorldFile, registry: &PackageRegistry, incoming: &[PackageMetadata]) {
+        loop {
+            let removing = |name: &str, version: &str| self.removals.iter().any(|r| r.name == name && r.version == version);
+            let mut required: Vec<String> = registry.list_packages().into_iter()
+                .filter(|p| !removing(&p.name, &p.version))
+                .flat_map(|p| p.dependencies.iter().cloned())
+                .collect();
+            required.extend(incoming.iter()
+                .flat_map(|p| p.dependencies.iter().flatten())
+                .map(|spec| resolver::parse_package_spec(spec).0.to_string()));
+
+            // Versions outside a listed package's constraint go either way.
+            let before = self.removals.len();
+            let kept: Vec<String> = self.removals.iter()
+                .filter(|r| world.get(&r.name).is_none() && required.contains(&r.name))
+                .map(|r| r.name.clone())
+                .collect();
+            self.removals.retain(|r| !kept.contains(&r.name));
+            for name in kept {
+                if !self.mark_dependency.contains(&name) {
+                    self.mark_dependency.push(name);
+                }
+            }
+            if self.removals.len() == before {
+                return;
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.removals.is_empty() && self.mark_explicit.is_empty() && self.mark_dependency.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::PackageSource;
+
+    fn installed(name: &str, version: &str, constellation: &str, reason: InstallReason, dependencies: &[&str]) -> InstalledPackage {
+        InstalledPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            install_path: PathBuf::from("/hoshi_packages").join(name).join(version),
+            installed_at: None,
+            reason,
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            files: Vec::new(),
+            executables: None,
+            source: Some(PackageSource {
+                constellation: constellation.to_string(),
+                download_url: format!("https://example.org/{}-{}.tar.gz", name, version),
+                checksum: String::new(),
+            }),
+            license: None,
+        }
+    }
+
+    fn world(json: &str) -> WorldFile {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn removal(name: &str, version: &str) -> PlanRemoval {
+        PlanRemoval { name: name.to_string(), version: version.to_string() }
+    }
+
+    #[test]
+    fn install_from_another_constellation_does_not_satisfy_a_pin() {
+        let mut registry = PackageRegistry::default();
+        registry.add(installed("libstar", "1.0", "Hoshi Core", InstallReason::Explicit, &[]));
+
+        let diff = WorldDiff::new(&world(r#"{"packages": ["libstar@hoshi extra"]}"#), &registry);
+        assert_eq!(diff.missing.len(), 1);
+        assert_eq!(diff.missing[0].constellation.as_deref(), Some("hoshi extra"));
+        assert_eq!(diff.removals, [removal("libstar", "1.0")]);
+
+        let diff = WorldDiff::new(&world(r#"{"packages": ["libstar@Hoshi Core"]}"#), &registry);
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn unlisted_package_still_required_is_kept_as_a_dependency() {
+        let mut registry = PackageRegistry::default();
+        registry.add(installed("comet", "2.1", "Hoshi Core", InstallReason::Explicit, &["libstar"]));
+        registry.add(installed("libstar", "1.0", "Hoshi Core", InstallReason::Explicit, &[]));
+        registry.add(installed("nebula", "0.4", "Hoshi Core", InstallReason::Explicit, &[]));
+        let world = world(r#"{"packages": ["comet"]}"#);
+
+        let mut diff = WorldDiff::new(&world, &registry);
+        assert_eq!(diff.removals, [removal("libstar", "1.0"), removal("nebula", "0.4")]);
+
+        diff.keep_required(&world, &registry, &[]);
+        assert_eq!(diff.removals, [removal("nebula", "0.4")]);
+        assert_eq!(diff.mark_dependency, ["libstar"]);
+    }
+}
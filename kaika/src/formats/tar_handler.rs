@@ -15,7 +15,7 @@ use std::io::{Read, Write, BufReader};
 fn get_entry_name(path: &Path) -> Result<PathBuf> {
     std::env::current_dir()
         .map(|cwd| path.strip_prefix(&cwd).unwrap_or(path).to_path_buf())
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Failed to get current directory: {}", e)))
+        .map_err(|e| io::Error::other(format!("Failed to get current directory: {}", e)))
 }
 
 async fn create_tar_with_compression<W: Write + 'static + Send + Unpin>(
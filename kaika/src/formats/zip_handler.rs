@@ -28,7 +28,7 @@ pub async fn create_zip_archive(archive_path: &Path, paths: &[PathBuf]) -> Resul
             let mut file_to_archive = File::open(path).await?.into_std().await;
             std::io::copy(&mut file_to_archive, &mut zip)?;
         } else if metadata.is_dir() {
-            zip.add_directory(&format!("{}/", filename), options)?;
+            zip.add_directory(format!("{}/", filename), options)?;
         } else {
             eprintln!("Warning: Skipping unsupported file type for zip: {}", path.display());
         }
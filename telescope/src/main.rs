@@ -1,51 +1,13 @@
 use clap::{Parser, Subcommand};
-use serde::{Deserialize, Serialize};
-use webfetch; // Use webfetch to fetch constellation metadata
-
-// Re-use structs from hoshipkg for package and constellation metadata
-// This assumes hoshipkg's structs are public (which they are) and compatible.
-// For a standalone tool, you might want a shared 'hoshi-core-types' crate
-// or define them directly here. For now, we'll define them here to avoid circular deps.
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct PackageMetadata {
-    pub name: String,
-    pub version: String,
-    pub size_mb: f64,
-    pub download_url: String,
-    pub archive_type: String,
-    pub dependencies: Option<Vec<String>>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ConstellationMetadata {
-    pub name: String,
-    #[serde(default)]
-    pub packages: Vec<PackageMetadata>,
-}
-
-#[derive(Debug)]
-pub struct Constellation {
-    pub name: String,
-    pub metadata_url: String,
-}
-
-impl Constellation {
-    pub fn default_constellations() -> Vec<Self> {
-        vec![
-            Constellation {
-                name: "Hoshi-Core".to_string(),
-                metadata_url: "http://localhost:8000/hoshi-core-constellation.json".to_string(),
-            },
-            Constellation {
-                name: "Hoshi-Extra".to_string(),
-                metadata_url: "http://localhost:8000/hoshi-extra-constellation.json".to_string(),
-            },
-        ]
-    }
-}
+use hoshi_core::fetch::fetch_metadata;
+use hoshi_core::{Constellation, PackageMetadata};
 
+/// Search for Hoshi packages
+///
+/// Searches the constellations configured for hpkg. Without a constellations config that is only
+/// the default Hoshi Core constellation; add others, such as Hoshi Extra, to the config to search them.
 #[derive(Parser, Debug)]
-#[command(author, version, about = "Search for Hoshi packages", long_about = None)]
+#[command(author, version)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
@@ -79,7 +41,7 @@ async fn handle_search_command(
     constellation_name: Option<&str>,
     query: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let constellations = Constellation::default_constellations();
+    let constellations = Constellation::load_all().await?;
     let mut all_packages: Vec<PackageMetadata> = Vec::new();
 
     let target_constellations = if let Some(name) = constellation_name {
@@ -96,19 +58,12 @@ async fn handle_search_command(
     println!("Searching for '{}' in selected constellations...", query);
 
     for constellation in target_constellations {
-        match webfetch::fetch_url_to_string(&constellation.metadata_url).await {
-            Ok(metadata_content) => {
-                match serde_json::from_str::<ConstellationMetadata>(&metadata_content) {
-                    Ok(meta) => {
-                        all_packages.extend(meta.packages);
-                    },
-                    Err(e) => {
-                        eprintln!("Error parsing metadata for {}: {}", constellation.name, e);
-                    }
-                }
+        match fetch_metadata(&constellation).await {
+            Ok((meta, _)) => {
+                all_packages.extend(meta.packages);
             },
             Err(e) => {
-                eprintln!("Error fetching metadata for {}: {}", constellation.name, e);
+                eprintln!("Error syncing metadata for {}: {}", constellation.name, e);
             }
         }
     }
@@ -124,7 +79,11 @@ async fn handle_search_command(
     } else {
         println!("\nFound packages:");
         for pkg in found_packages {
-            println!("  {} v{} ({} MB)", pkg.name, pkg.version, pkg.size_mb);
+            println!("  {} v{} from {} ({} MB)", pkg.name, pkg.version, pkg.constellation, pkg.size_mb);
+            println!("      {}", pkg.description);
+            if let Some(license) = &pkg.license {
+                println!("      License: {}", license);
+            }
         }
     }
 
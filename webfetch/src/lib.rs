@@ -1,10 +1,11 @@
 // hoshi/webfetch/src/lib.rs
-use reqwest::Client;
+pub use reqwest::Client;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 use futures_util::stream::StreamExt; // <-- CHANGED THIS LINE
 use std::path::{Path, PathBuf};
 use std::error::Error;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 // REMOVED: use std::io::Result as IoResult;
 // REMOVED: use std::time::Instant;
@@ -23,6 +24,14 @@ pub async fn fetch_url_to_string(url: &str) -> Result<String, Box<dyn Error + Se
     Ok(text)
 }
 
+/// Times a HEAD request against `url`, used to rank mirrors.
+pub async fn measure_latency(url: &str) -> Result<Duration, Box<dyn Error + Send + Sync>> {
+    let client = Client::builder().timeout(Duration::from_secs(5)).build()?;
+    let start = Instant::now();
+    client.head(url).send().await?.error_for_status()?;
+    Ok(start.elapsed())
+}
+
 pub fn get_temp_download_dir() -> PathBuf {
     std::env::temp_dir().join("hoshi_downloads_temp")
 }
@@ -33,7 +42,17 @@ pub async fn download_file_with_progress(
     file_name: &str,
     tx: mpsc::Sender<DownloadProgress>,
 ) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
-    let client = Client::new();
+    download_file_with_client(&Client::new(), url, target_dir, file_name, tx).await
+}
+
+/// Same as `download_file_with_progress`, but reuses the caller's client and its connection pool.
+pub async fn download_file_with_client(
+    client: &Client,
+    url: &str,
+    target_dir: &Path,
+    file_name: &str,
+    tx: mpsc::Sender<DownloadProgress>,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
     let response = client.get(url).send().await?.error_for_status()?;
 
     let total_size = response.content_length();
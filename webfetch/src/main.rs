@@ -2,7 +2,6 @@
 use std::env;
 use std::path::PathBuf;
 use tokio::sync::mpsc;
-use tokio::task::JoinError; 
 
 use webfetch::{download_file_with_progress, DownloadProgress};
 